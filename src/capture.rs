@@ -0,0 +1,33 @@
+use std::fs::File;
+use std::io::Write;
+
+use crate::profiler::StatementTiming;
+
+// Records the statements of a session as a script that can be replayed by
+// piping it into the monitor, e.g. `PlecakDB < workload.sql`. Each statement
+// is preceded by a comment with its original timing, and the script turns the
+// timer on so a replay prints the new timings next to them.
+pub struct Capture {
+    path: String,
+    file: File,
+}
+
+impl Capture {
+    pub fn start(path: &str) -> Result<Self, String> {
+        let mut file = File::create(path).map_err(|error| format!("Cannot create {}: {}", path, error))?;
+        writeln!(file, ".timer on").map_err(|error| format!("Cannot write to {}: {}", path, error))?;
+        Ok(Capture {
+            path: path.to_string(),
+            file,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn record(&mut self, command: &str, timing: &StatementTiming) -> Result<(), String> {
+        writeln!(self.file, "-- {}\n{}", timing, command)
+            .map_err(|error| format!("Cannot write to {}: {}", self.path, error))
+    }
+}
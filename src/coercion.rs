@@ -0,0 +1,211 @@
+use crate::decimal::{self, Decimal};
+use crate::parser::{DataType, Value};
+use crate::temporal::{Date, Timestamp};
+
+const MICROS_PER_DAY: i64 = 86_400 * 1_000_000;
+
+// Implicit coercions, applied when two values of different types meet in a
+// comparison. Both sides are converted to the common type:
+//
+//   INTEGER   with FLOAT       -> FLOAT
+//   INTEGER   with DECIMAL     -> DECIMAL
+//   FLOAT     with DECIMAL     -> FLOAT
+//   DATE      with TIMESTAMP   -> TIMESTAMP
+//   DATE      with TIMESTAMPTZ -> TIMESTAMPTZ
+//   TIMESTAMP with TIMESTAMPTZ -> TIMESTAMPTZ
+//
+// A DATE or TIMESTAMP has no zone, so when it meets a TIMESTAMPTZ it is read as
+// local time in the session's timezone setting (a DATE as its midnight) and
+// converted to UTC, the zone TIMESTAMPTZ values are kept in.
+//
+// Any other mix, including numbers with TEXT, is an error and needs a CAST.
+pub fn common_type(left: &DataType, right: &DataType) -> Option<DataType> {
+    use DataType::*;
+    match (left, right) {
+        (Decimal { precision: p1, scale: s1 }, Decimal { precision: p2, scale: s2 }) => Some(Decimal {
+            precision: (*p1).max(*p2),
+            scale: (*s1).max(*s2),
+        }),
+        (left, right) if left == right => Some(left.clone()),
+        (Integer, Float) | (Float, Integer) => Some(Float),
+        (Integer, Decimal { .. }) => Some(right.clone()),
+        (Decimal { .. }, Integer) => Some(left.clone()),
+        (Float, Decimal { .. }) | (Decimal { .. }, Float) => Some(Float),
+        (Date, Timestamp) | (Timestamp, Date) => Some(Timestamp),
+        (Date | Timestamp, TimestampTz) | (TimestampTz, Date | Timestamp) => Some(TimestampTz),
+        _ => None,
+    }
+}
+
+pub fn value_type(value: &Value) -> Option<DataType> {
+    match value {
+        Value::Null => None,
+        Value::Integer(_) => Some(DataType::Integer),
+        Value::Float(_) => Some(DataType::Float),
+        Value::Text(_) => Some(DataType::Text),
+        Value::Boolean(_) => Some(DataType::Boolean),
+        Value::Date(_) => Some(DataType::Date),
+        Value::Timestamp(_) => Some(DataType::Timestamp),
+        Value::TimestampTz(_) => Some(DataType::TimestampTz),
+        Value::Decimal(decimal) => Some(DataType::Decimal {
+            precision: decimal.precision(),
+            scale: decimal.scale(),
+        }),
+        Value::Blob(_) => Some(DataType::Blob),
+    }
+}
+
+pub fn type_name(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Integer => "INTEGER".to_string(),
+        DataType::Float => "FLOAT".to_string(),
+        DataType::Text => "TEXT".to_string(),
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Date => "DATE".to_string(),
+        DataType::Timestamp => "TIMESTAMP".to_string(),
+        DataType::TimestampTz => "TIMESTAMPTZ".to_string(),
+        DataType::Decimal { precision, scale } => format!("DECIMAL({}, {})", precision, scale),
+        DataType::Blob => "BLOB".to_string(),
+    }
+}
+
+// Explicit conversions done by CAST(value AS type). NULL casts to NULL of any type.
+//
+//   from \ to   INTEGER  FLOAT  DECIMAL  TEXT  BOOLEAN  DATE  TIMESTAMP[TZ]  BLOB
+//   INTEGER        x       x       x      x       x
+//   FLOAT          x       x       x      x
+//   DECIMAL        x       x       x      x
+//   TEXT           x       x       x      x       x      x         x          x
+//   BOOLEAN        x       x              x       x
+//   DATE                                  x              x         x
+//   TIMESTAMP[TZ]                         x              x         x
+//   BLOB                                                                      x
+//
+// Numbers are rounded half away from zero when digits are dropped. Timestamps
+// without a zone are taken to be UTC.
+pub fn cast(value: Value, target: &DataType) -> Result<Value, String> {
+    let source = match value_type(&value) {
+        Some(source) => source,
+        None => return Ok(Value::Null),
+    };
+    let invalid = || format!("Cannot cast {} to {}", type_name(&source), type_name(target));
+
+    match (value, target) {
+        (Value::Integer(integer), DataType::Integer) => Ok(Value::Integer(integer)),
+        (Value::Float(float), DataType::Integer) => {
+            let rounded = float.round();
+            if !rounded.is_finite() || rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+                return Err(format!("Float {} is out of range for INTEGER", float));
+            }
+            Ok(Value::Integer(rounded as i64))
+        }
+        (Value::Decimal(decimal), DataType::Integer) => {
+            let units = decimal.rescale(0)?.units();
+            i64::try_from(units)
+                .map(Value::Integer)
+                .map_err(|_| format!("Decimal {:?} is out of range for INTEGER", decimal))
+        }
+        (Value::Text(text), DataType::Integer) => text
+            .trim()
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("Cannot cast '{}' to INTEGER", text)),
+        (Value::Boolean(boolean), DataType::Integer) => Ok(Value::Integer(boolean as i64)),
+
+        (Value::Integer(integer), DataType::Float) => Ok(Value::Float(integer as f64)),
+        (Value::Float(float), DataType::Float) => Ok(Value::Float(float)),
+        (Value::Decimal(decimal), DataType::Float) => {
+            Ok(Value::Float(decimal.units() as f64 / 10f64.powi(decimal.scale() as i32)))
+        }
+        (Value::Text(text), DataType::Float) => text
+            .trim()
+            .parse()
+            .map(Value::Float)
+            .map_err(|_| format!("Cannot cast '{}' to FLOAT", text)),
+        (Value::Boolean(boolean), DataType::Float) => Ok(Value::Float(boolean as i64 as f64)),
+
+        (Value::Integer(integer), DataType::Decimal { precision, scale }) => {
+            Ok(Value::Decimal(Decimal::new(integer as i128, 0)?.fit(*precision, *scale)?))
+        }
+        (Value::Float(float), DataType::Decimal { precision, scale }) => {
+            if !float.is_finite() {
+                return Err(format!("Float {} cannot be cast to DECIMAL", float));
+            }
+            // Round to the target scale first so long binary fractions do not overflow the digit limit.
+            let text = format!("{:.*}", (*scale).min(decimal::MAX_PRECISION) as usize, float);
+            Ok(Value::Decimal(Decimal::parse(&text)?.fit(*precision, *scale)?))
+        }
+        (Value::Decimal(decimal), DataType::Decimal { precision, scale }) => {
+            Ok(Value::Decimal(decimal.fit(*precision, *scale)?))
+        }
+        (Value::Text(text), DataType::Decimal { precision, scale }) => {
+            Ok(Value::Decimal(Decimal::parse(&text)?.fit(*precision, *scale)?))
+        }
+
+        (Value::Integer(integer), DataType::Text) => Ok(Value::Text(integer.to_string())),
+        (Value::Float(float), DataType::Text) => Ok(Value::Text(float.to_string())),
+        (Value::Decimal(decimal), DataType::Text) => Ok(Value::Text(format!("{:?}", decimal))),
+        (Value::Text(text), DataType::Text) => Ok(Value::Text(text)),
+        (Value::Boolean(boolean), DataType::Text) => Ok(Value::Text(boolean.to_string())),
+        (Value::Date(date), DataType::Text) => Ok(Value::Text(format!("{:?}", date))),
+        (Value::Timestamp(timestamp), DataType::Text) => Ok(Value::Text(format!("{:?}", timestamp))),
+        (Value::TimestampTz(timestamp), DataType::Text) => Ok(Value::Text(format!("{:?}+00:00", timestamp))),
+
+        (Value::Integer(integer), DataType::Boolean) => Ok(Value::Boolean(integer != 0)),
+        (Value::Boolean(boolean), DataType::Boolean) => Ok(Value::Boolean(boolean)),
+        (Value::Text(text), DataType::Boolean) => match text.trim().to_lowercase().as_str() {
+            "true" | "t" | "yes" | "1" => Ok(Value::Boolean(true)),
+            "false" | "f" | "no" | "0" => Ok(Value::Boolean(false)),
+            _ => Err(format!("Cannot cast '{}' to BOOLEAN", text)),
+        },
+
+        (Value::Text(text), DataType::Date) => Ok(Value::Date(Date::parse(&text)?)),
+        (Value::Date(date), DataType::Date) => Ok(Value::Date(date)),
+        (Value::Timestamp(timestamp) | Value::TimestampTz(timestamp), DataType::Date) => {
+            Ok(Value::Date(Date::from_days(timestamp.micros().div_euclid(MICROS_PER_DAY))))
+        }
+
+        (Value::Text(text), DataType::Timestamp) => Ok(Value::Timestamp(Timestamp::parse(&text)?)),
+        (Value::Text(text), DataType::TimestampTz) => Ok(Value::TimestampTz(Timestamp::parse_with_zone(&text)?)),
+        (Value::Date(date), DataType::Timestamp) => Ok(Value::Timestamp(start_of_day(date)?)),
+        (Value::Date(date), DataType::TimestampTz) => Ok(Value::TimestampTz(start_of_day(date)?)),
+        (Value::Timestamp(timestamp) | Value::TimestampTz(timestamp), DataType::Timestamp) => {
+            Ok(Value::Timestamp(timestamp))
+        }
+        (Value::Timestamp(timestamp) | Value::TimestampTz(timestamp), DataType::TimestampTz) => {
+            Ok(Value::TimestampTz(timestamp))
+        }
+
+        (Value::Text(text), DataType::Blob) => Ok(Value::Blob(text.into_bytes())),
+        (Value::Blob(bytes), DataType::Blob) => Ok(Value::Blob(bytes)),
+
+        _ => Err(invalid()),
+    }
+}
+
+fn start_of_day(date: Date) -> Result<Timestamp, String> {
+    date.days()
+        .checked_mul(MICROS_PER_DAY)
+        .map(Timestamp::from_micros)
+        .ok_or_else(|| format!("Date {:?} is out of range for TIMESTAMP", date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoned_and_local_timestamps_meet_as_timestamptz() {
+        for local in [DataType::Date, DataType::Timestamp] {
+            assert_eq!(common_type(&local, &DataType::TimestampTz), Some(DataType::TimestampTz));
+            assert_eq!(common_type(&DataType::TimestampTz, &local), Some(DataType::TimestampTz));
+        }
+        assert_eq!(common_type(&DataType::Date, &DataType::Timestamp), Some(DataType::Timestamp));
+    }
+
+    #[test]
+    fn temporal_types_do_not_mix_with_text_or_numbers() {
+        assert_eq!(common_type(&DataType::TimestampTz, &DataType::Text), None);
+        assert_eq!(common_type(&DataType::Timestamp, &DataType::Integer), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+pub const MAX_PRECISION: u32 = 38;
+
+// A fixed-point number: `units / 10^scale`.
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    units: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(units: i128, scale: u32) -> Result<Self, String> {
+        if scale > MAX_PRECISION {
+            return Err(format!("Decimal scale {} exceeds the maximum of {}", scale, MAX_PRECISION));
+        }
+        Ok(Decimal { units, scale })
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let trimmed = text.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+
+        let (integer_part, fraction_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer_part.is_empty() && fraction_part.is_empty()
+            || !integer_part.chars().chain(fraction_part.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(format!("Invalid decimal '{}'", text));
+        }
+        if integer_part.len() + fraction_part.len() > MAX_PRECISION as usize {
+            return Err(format!("Decimal '{}' has more than {} digits", text, MAX_PRECISION));
+        }
+
+        let mut units: i128 = 0;
+        for c in integer_part.chars().chain(fraction_part.chars()) {
+            units = units * 10 + c.to_digit(10).unwrap() as i128;
+        }
+        if negative {
+            units = -units;
+        }
+        Decimal::new(units, fraction_part.len() as u32)
+    }
+
+    pub fn rescale(&self, scale: u32) -> Result<Decimal, String> {
+        if scale >= self.scale {
+            let factor = pow10(scale - self.scale)?;
+            let units = self.units.checked_mul(factor).ok_or("Decimal overflow")?;
+            Decimal::new(units, scale)
+        } else {
+            // Round half away from zero when dropping digits.
+            let factor = pow10(self.scale - scale)?;
+            let mut units = self.units / factor;
+            let remainder = self.units % factor;
+            if remainder.abs() >= factor - remainder.abs() {
+                units += self.units.signum();
+            }
+            Decimal::new(units, scale)
+        }
+    }
+}
+
+// Exact arithmetic for the expression evaluator.
+#[allow(dead_code)]
+impl Decimal {
+    pub fn precision(&self) -> u32 {
+        let digits = self.units.unsigned_abs().checked_ilog10().map_or(1, |log| log + 1);
+        digits.max(self.scale)
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Result<Decimal, String> {
+        let scale = self.scale.max(other.scale);
+        let left = self.rescale(scale)?;
+        let right = other.rescale(scale)?;
+        let units = left.units.checked_add(right.units).ok_or("Decimal overflow")?;
+        Decimal::new(units, scale)
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Result<Decimal, String> {
+        let negated = Decimal::new(other.units.checked_neg().ok_or("Decimal overflow")?, other.scale)?;
+        self.checked_add(&negated)
+    }
+
+    pub fn checked_mul(&self, other: &Decimal) -> Result<Decimal, String> {
+        let units = self.units.checked_mul(other.units).ok_or("Decimal overflow")?;
+        Decimal::new(units, self.scale + other.scale)
+    }
+
+    // Drops the digits past `scale`, rounding toward zero.
+    pub fn truncate(&self, scale: u32) -> Result<Decimal, String> {
+        if scale >= self.scale {
+            return Ok(*self);
+        }
+        Decimal::new(self.units / pow10(self.scale - scale)?, scale)
+    }
+
+    // Checks that the value fits a DECIMAL(precision, scale) column.
+    pub fn fit(&self, precision: u32, scale: u32) -> Result<Decimal, String> {
+        let rescaled = self.rescale(scale)?;
+        if rescaled.precision() > precision {
+            return Err(format!("Value {:?} does not fit DECIMAL({}, {})", self, precision, scale));
+        }
+        Ok(rescaled)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Ok(left), Ok(right)) => left.units.cmp(&right.units),
+            // Rescaling only overflows for values of huge magnitude, whose sign decides.
+            (Err(_), _) => if self.units < 0 { Ordering::Less } else { Ordering::Greater },
+            (_, Err(_)) => if other.units < 0 { Ordering::Greater } else { Ordering::Less },
+        }
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.units.unsigned_abs().to_string();
+        let sign = if self.units < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer_part, fraction_part) = padded.split_at(padded.len() - scale);
+        write!(f, "{}{}.{}", sign, integer_part, fraction_part)
+    }
+}
+
+fn pow10(exponent: u32) -> Result<i128, String> {
+    10_i128.checked_pow(exponent).ok_or_else(|| "Decimal overflow".to_string())
+}
@@ -0,0 +1,815 @@
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::coercion::{cast, common_type, type_name, value_type};
+use crate::decimal::Decimal;
+use crate::digest;
+use crate::parser::{BinaryOperator, DataType, Value};
+use crate::session::Session;
+use crate::temporal::Timestamp;
+
+const STRING_FUNCTIONS: [&str; 8] = ["TRIM", "LTRIM", "RTRIM", "REPLACE", "SPLIT_PART", "INSTR", "LPAD", "RPAD"];
+
+const DATE_PARTS: [&str; 7] = ["year", "month", "day", "hour", "minute", "second", "dow"];
+const MICROS_PER_SECOND: i64 = 1_000_000;
+
+// Longest text LPAD and RPAD build, so a mistyped length cannot exhaust memory.
+const MAX_PAD_LENGTH: i64 = 1 << 24;
+
+// Operations on single values, used to run a SELECT without FROM, which reads
+// no tables. A NULL operand gives NULL unless noted otherwise.
+
+// Arithmetic converts both operands to their common numeric type first, so
+// 1 + 2.5 is a FLOAT. Decimals are divided as FLOATs.
+pub fn binary(operator: BinaryOperator, left: Value, right: Value) -> Result<Value, String> {
+    if left == Value::Null || right == Value::Null {
+        return Ok(Value::Null);
+    }
+    if operator == BinaryOperator::Concat {
+        return Ok(Value::Text(text(left)? + &text(right)?));
+    }
+
+    let (left_type, right_type) = (value_type(&left), value_type(&right));
+    let invalid = || match (&left_type, &right_type) {
+        (Some(left_type), Some(right_type)) => format!(
+            "Operator {} cannot be applied to {} and {}",
+            operator.symbol(),
+            type_name(left_type),
+            type_name(right_type)
+        ),
+        _ => format!("Operator {} cannot be applied to NULL", operator.symbol()),
+    };
+    let common = left_type.as_ref().zip(right_type.as_ref()).and_then(|(left, right)| common_type(left, right));
+    match common.ok_or_else(invalid)? {
+        DataType::Integer => match (left, right) {
+            (Value::Integer(left), Value::Integer(right)) => integer_operation(operator, left, right),
+            _ => Err(invalid()),
+        },
+        DataType::Decimal { .. } if !matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo) => {
+            match (to_decimal(left), to_decimal(right)) {
+                (Some(left), Some(right)) => decimal_operation(operator, &left, &right).ok_or_else(invalid)?,
+                _ => Err(invalid()),
+            }
+        }
+        DataType::Float | DataType::Decimal { .. } => match (cast(left, &DataType::Float)?, cast(right, &DataType::Float)?) {
+            (Value::Float(left), Value::Float(right)) => float_operation(operator, left, right).ok_or_else(invalid)?,
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+fn integer_operation(operator: BinaryOperator, left: i64, right: i64) -> Result<Value, String> {
+    let result = match operator {
+        BinaryOperator::Divide | BinaryOperator::Modulo if right == 0 => return Err("Division by zero".to_string()),
+        BinaryOperator::Add => left.checked_add(right),
+        BinaryOperator::Subtract => left.checked_sub(right),
+        BinaryOperator::Multiply => left.checked_mul(right),
+        BinaryOperator::Divide => left.checked_div(right),
+        BinaryOperator::Modulo => left.checked_rem(right),
+        BinaryOperator::BitAnd => Some(left & right),
+        BinaryOperator::BitOr => Some(left | right),
+        BinaryOperator::ShiftLeft => u32::try_from(right).ok().and_then(|shift| left.checked_shl(shift)),
+        BinaryOperator::ShiftRight => u32::try_from(right).ok().and_then(|shift| left.checked_shr(shift)),
+        BinaryOperator::Concat => unreachable!("|| is applied before numeric coercion"),
+    };
+    result
+        .map(Value::Integer)
+        .ok_or_else(|| format!("Integer out of range: {} {} {}", left, operator.symbol(), right))
+}
+
+// None if the operator does not apply to decimals.
+fn decimal_operation(operator: BinaryOperator, left: &Decimal, right: &Decimal) -> Option<Result<Value, String>> {
+    let result = match operator {
+        BinaryOperator::Add => left.checked_add(right),
+        BinaryOperator::Subtract => left.checked_sub(right),
+        BinaryOperator::Multiply => left.checked_mul(right),
+        _ => return None,
+    };
+    Some(result.map(Value::Decimal))
+}
+
+// None if the operator does not apply to floats.
+fn float_operation(operator: BinaryOperator, left: f64, right: f64) -> Option<Result<Value, String>> {
+    let result = match operator {
+        BinaryOperator::Divide | BinaryOperator::Modulo if right == 0.0 => return Some(Err("Division by zero".to_string())),
+        BinaryOperator::Add => left + right,
+        BinaryOperator::Subtract => left - right,
+        BinaryOperator::Multiply => left * right,
+        BinaryOperator::Divide => left / right,
+        BinaryOperator::Modulo => left % right,
+        _ => return None,
+    };
+    if !result.is_finite() {
+        return Some(Err(format!("Float out of range: {} {} {}", left, operator.symbol(), right)));
+    }
+    Some(Ok(Value::Float(result)))
+}
+
+fn to_decimal(value: Value) -> Option<Decimal> {
+    match value {
+        Value::Integer(integer) => Decimal::new(integer as i128, 0).ok(),
+        Value::Decimal(decimal) => Some(decimal),
+        _ => None,
+    }
+}
+
+fn text(value: Value) -> Result<String, String> {
+    match cast(value, &DataType::Text)? {
+        Value::Text(text) => Ok(text),
+        other => Err(format!("Cannot convert {:?} to TEXT", other)),
+    }
+}
+
+// None if either side is NULL. Values of different types are compared in their
+// common type, as in coercion::common_type.
+pub fn compare(left: Value, right: Value) -> Result<Option<Ordering>, String> {
+    let (left_type, right_type) = match (value_type(&left), value_type(&right)) {
+        (Some(left_type), Some(right_type)) => (left_type, right_type),
+        _ => return Ok(None),
+    };
+    let common = common_type(&left_type, &right_type).ok_or_else(|| {
+        format!("Cannot compare {} with {}", type_name(&left_type), type_name(&right_type))
+    })?;
+    // The zone-less side would be read in the session timezone, which is not applied here.
+    if common == DataType::TimestampTz && left_type != right_type {
+        return Err("Comparing DATE or TIMESTAMP with TIMESTAMPTZ is not supported in a SELECT without FROM".to_string());
+    }
+
+    let ordering = match common {
+        DataType::Decimal { .. } => to_decimal(left).zip(to_decimal(right)).map(|(left, right)| left.cmp(&right)),
+        _ => match (cast(left, &common)?, cast(right, &common)?) {
+            (Value::Integer(left), Value::Integer(right)) => Some(left.cmp(&right)),
+            (Value::Float(left), Value::Float(right)) => left.partial_cmp(&right),
+            (Value::Text(left), Value::Text(right)) => Some(left.cmp(&right)),
+            (Value::Boolean(left), Value::Boolean(right)) => Some(left.cmp(&right)),
+            (Value::Date(left), Value::Date(right)) => Some(left.days().cmp(&right.days())),
+            (Value::Timestamp(left), Value::Timestamp(right)) | (Value::TimestampTz(left), Value::TimestampTz(right)) => {
+                Some(left.micros().cmp(&right.micros()))
+            }
+            (Value::Blob(left), Value::Blob(right)) => Some(left.cmp(&right)),
+            _ => None,
+        },
+    };
+    Ok(ordering)
+}
+
+// Scalar functions whose arguments are all evaluated first. COALESCE, IFNULL
+// and IIF skip arguments they do not return, so the caller handles them.
+pub fn call(name: &str, arguments: Vec<Value>, session: &Session) -> Result<Value, String> {
+    if name == "GREATEST" || name == "LEAST" {
+        let wanted = if name == "GREATEST" { Ordering::Greater } else { Ordering::Less };
+        let mut result = Value::Null;
+        for value in arguments {
+            if result == Value::Null || compare(value.clone(), result.clone())? == Some(wanted) {
+                result = value;
+            }
+        }
+        return Ok(result);
+    }
+    if STRING_FUNCTIONS.contains(&name) {
+        return string_function(name, &arguments);
+    }
+
+    let count = arguments.len();
+    let mut arguments = arguments.into_iter();
+    let mut next = || arguments.next().unwrap_or(Value::Null);
+    match name {
+        "UPPER" => map_text(name, next(), |text| text.to_uppercase()),
+        "LOWER" => map_text(name, next(), |text| text.to_lowercase()),
+        "LENGTH" => match next() {
+            Value::Null => Ok(Value::Null),
+            Value::Text(text) => Ok(Value::Integer(text.chars().count() as i64)),
+            Value::Blob(bytes) => Ok(Value::Integer(bytes.len() as i64)),
+            other => Err(format!("LENGTH expects TEXT or BLOB, got {}", value_type_name(&other))),
+        },
+        "SUBSTR" => match (next(), next(), next()) {
+            (Value::Null, _, _) | (_, Value::Null, _) => Ok(Value::Null),
+            (_, _, Value::Null) if count == 3 => Ok(Value::Null),
+            (Value::Text(text), Value::Integer(start), length) => {
+                let length = match length {
+                    Value::Null => None,
+                    Value::Integer(length) if length < 0 => return Err(format!("SUBSTR length cannot be negative, got {}", length)),
+                    Value::Integer(length) => Some(length),
+                    other => return Err(format!("SUBSTR expects an INTEGER length, got {}", value_type_name(&other))),
+                };
+                Ok(Value::Text(substring(&text, start, length)))
+            }
+            (Value::Text(_), other, _) => Err(format!("SUBSTR expects an INTEGER start, got {}", value_type_name(&other))),
+            (other, _, _) => Err(format!("SUBSTR expects TEXT, got {}", value_type_name(&other))),
+        },
+        "ABS" => match next() {
+            Value::Null => Ok(Value::Null),
+            Value::Integer(integer) => integer
+                .checked_abs()
+                .map(Value::Integer)
+                .ok_or_else(|| format!("Integer out of range: ABS({})", integer)),
+            Value::Float(float) => Ok(Value::Float(float.abs())),
+            Value::Decimal(decimal) => match decimal.units().checked_abs() {
+                Some(units) => Ok(Value::Decimal(Decimal::new(units, decimal.scale())?)),
+                None => Err(format!("Decimal out of range: ABS({:?})", decimal)),
+            },
+            other => Err(format!("ABS expects a number, got {}", value_type_name(&other))),
+        },
+        "FLOOR" | "CEIL" => match next() {
+            Value::Null => Ok(Value::Null),
+            Value::Integer(integer) => Ok(Value::Integer(integer)),
+            Value::Float(float) => Ok(Value::Float(if name == "FLOOR" { float.floor() } else { float.ceil() })),
+            Value::Decimal(decimal) => {
+                let truncated = decimal.truncate(0)?;
+                let step = match decimal.cmp(&truncated) {
+                    Ordering::Less if name == "FLOOR" => -1,
+                    Ordering::Greater if name == "CEIL" => 1,
+                    _ => 0,
+                };
+                Ok(Value::Decimal(truncated.checked_add(&Decimal::new(step, 0)?)?))
+            }
+            other => Err(format!("{} expects a number, got {}", name, value_type_name(&other))),
+        },
+        "POWER" => match (float_argument(name, next())?, float_argument(name, next())?) {
+            (Some(base), Some(exponent)) if (base == 0.0 && exponent < 0.0) || (base < 0.0 && exponent.fract() != 0.0) => {
+                domain_error(session, format!("POWER({}, {}) is undefined", base, exponent))
+            }
+            (Some(base), Some(exponent)) => float_result(name, base.powf(exponent)),
+            _ => Ok(Value::Null),
+        },
+        "SQRT" => match float_argument(name, next())? {
+            Some(float) if float < 0.0 => domain_error(session, format!("SQRT({}) is undefined", float)),
+            Some(float) => Ok(Value::Float(float.sqrt())),
+            None => Ok(Value::Null),
+        },
+        "LOG" => {
+            let first = float_argument(name, next())?;
+            let (base, float) = if count == 2 { (first, float_argument(name, next())?) } else { (Some(10.0), first) };
+            match (base, float) {
+                (Some(base), Some(float)) if float <= 0.0 || base <= 0.0 || base == 1.0 => {
+                    domain_error(session, format!("LOG of {} in base {} is undefined", float, base))
+                }
+                (Some(base), Some(float)) => float_result(name, if base == 10.0 { float.log10() } else { float.log(base) }),
+                _ => Ok(Value::Null),
+            }
+        }
+        "EXP" => match float_argument(name, next())? {
+            Some(float) => float_result(name, float.exp()),
+            None => Ok(Value::Null),
+        },
+        "SIGN" => match next() {
+            Value::Null => Ok(Value::Null),
+            Value::Integer(integer) => Ok(Value::Integer(integer.signum())),
+            Value::Float(float) => Ok(Value::Integer(if float > 0.0 { 1 } else if float < 0.0 { -1 } else { 0 })),
+            Value::Decimal(decimal) => Ok(Value::Integer(decimal.units().signum() as i64)),
+            other => Err(format!("SIGN expects a number, got {}", value_type_name(&other))),
+        },
+        "ROUND" | "TRUNC" => {
+            let value = next();
+            match digits_argument(name, next(), count)? {
+                Some(digits) => round_to(name, value, digits),
+                None => Ok(Value::Null),
+            }
+        }
+        "MOD" => match (next(), next()) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Integer(_), Value::Integer(0)) => domain_error(session, "Division by zero".to_string()),
+            // Only i64::MIN % -1 overflows, and its remainder is 0.
+            (Value::Integer(left), Value::Integer(right)) => Ok(Value::Integer(left.checked_rem(right).unwrap_or(0))),
+            (left, right) => match (float_argument(name, left)?, float_argument(name, right)?) {
+                (Some(_), Some(0.0)) => domain_error(session, "Division by zero".to_string()),
+                (Some(left), Some(right)) => Ok(Value::Float(left % right)),
+                _ => Ok(Value::Null),
+            },
+        },
+        "MD5" | "SHA256" | "HEX" | "BASE64" => {
+            // Text is hashed and encoded as its UTF-8 bytes.
+            let bytes = match next() {
+                Value::Null => return Ok(Value::Null),
+                Value::Text(text) => text.into_bytes(),
+                Value::Blob(bytes) => bytes,
+                other => return Err(format!("{} expects TEXT or BLOB, got {}", name, value_type_name(&other))),
+            };
+            Ok(Value::Text(match name {
+                "MD5" => digest::hex(&digest::md5(&bytes), false),
+                "SHA256" => digest::hex(&digest::sha256(&bytes), false),
+                "HEX" => digest::hex(&bytes, true),
+                _ => digest::base64(&bytes),
+            }))
+        }
+        "NULLIF" => {
+            let (value, other) = (next(), next());
+            match compare(value.clone(), other)? {
+                Some(Ordering::Equal) => Ok(Value::Null),
+                _ => Ok(value),
+            }
+        }
+        "TYPEOF" => Ok(Value::Text(value_type_name(&next()).to_string())),
+        "NOW" => Ok(Value::TimestampTz(now()?)),
+        "DATE" | "STRFTIME" => {
+            let format = match name {
+                "STRFTIME" => match next() {
+                    Value::Null => return Ok(Value::Null),
+                    Value::Text(format) => Some(format),
+                    other => return Err(format!("STRFTIME expects a TEXT format, got {}", value_type_name(&other))),
+                },
+                _ => None,
+            };
+            let Some(mut timestamp) = timestamp_argument(name, next())? else {
+                return Ok(Value::Null);
+            };
+            let modifiers = if format.is_some() { count.saturating_sub(2) } else { count.saturating_sub(1) };
+            for _ in 0..modifiers {
+                timestamp = match next() {
+                    Value::Null => return Ok(Value::Null),
+                    Value::Text(modifier) => timestamp.apply_modifier(&modifier)?,
+                    other => return Err(format!("{} expects TEXT modifiers, got {}", name, value_type_name(&other))),
+                };
+            }
+            match format {
+                Some(format) => strftime(&format, timestamp).map(Value::Text),
+                None => Ok(Value::Date(timestamp.date())),
+            }
+        }
+        "DATE_PART" => {
+            let part = match next() {
+                Value::Null => return Ok(Value::Null),
+                Value::Text(part) => part.to_lowercase(),
+                other => return Err(format!("DATE_PART expects a TEXT part, got {}", value_type_name(&other))),
+            };
+            let Some(timestamp) = timestamp_argument(name, next())? else {
+                return Ok(Value::Null);
+            };
+            let (year, month, day) = timestamp.date().civil();
+            let seconds = timestamp.micros_of_day() / MICROS_PER_SECOND;
+            let value = match part.as_str() {
+                "year" => year,
+                "month" => month as i64,
+                "day" => day as i64,
+                "hour" => seconds / 3600,
+                "minute" => seconds % 3600 / 60,
+                "second" => seconds % 60,
+                "dow" => timestamp.date().day_of_week() as i64,
+                _ => return Err(format!("Unknown date part '{}', expected one of: {}", part, DATE_PARTS.join(", "))),
+            };
+            Ok(Value::Integer(value))
+        }
+        _ => Err(format!("{}() is not supported in a SELECT without FROM", name)),
+    }
+}
+
+// A math domain error gives NULL or an error, as the math_domain_errors setting says.
+fn domain_error(session: &Session, message: String) -> Result<Value, String> {
+    match session.math_domain_errors() {
+        "null" => Ok(Value::Null),
+        _ => Err(message),
+    }
+}
+
+// A numeric argument as a FLOAT, None if it is NULL.
+fn float_argument(name: &str, value: Value) -> Result<Option<f64>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Integer(_) | Value::Float(_) | Value::Decimal(_) => match cast(value, &DataType::Float)? {
+            Value::Float(float) => Ok(Some(float)),
+            other => Err(format!("Cannot convert {:?} to FLOAT", other)),
+        },
+        other => Err(format!("{} expects a number, got {}", name, value_type_name(&other))),
+    }
+}
+
+fn float_result(name: &str, result: f64) -> Result<Value, String> {
+    if !result.is_finite() {
+        return Err(format!("Float out of range in {}", name));
+    }
+    Ok(Value::Float(result))
+}
+
+// The optional digits argument of ROUND and TRUNC, 0 when it is left out; None if it is NULL.
+fn digits_argument(name: &str, value: Value, count: usize) -> Result<Option<i64>, String> {
+    match value {
+        Value::Null if count < 2 => Ok(Some(0)),
+        Value::Null => Ok(None),
+        Value::Integer(digits) => Ok(Some(digits)),
+        other => Err(format!("{} expects INTEGER digits, got {}", name, value_type_name(&other))),
+    }
+}
+
+// Rounds to `digits` decimal places, keeping the type of the value: half away
+// from zero for ROUND, toward zero for TRUNC. Negative digits round to tens,
+// hundreds and so on.
+fn round_to(name: &str, value: Value, digits: i64) -> Result<Value, String> {
+    let half_away_from_zero = name == "ROUND";
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Integer(integer) if digits >= 0 => Ok(Value::Integer(integer)),
+        Value::Integer(integer) => round_units(integer as i128, digits.unsigned_abs(), half_away_from_zero)
+            .and_then(|units| i64::try_from(units).ok())
+            .map(Value::Integer)
+            .ok_or_else(|| format!("Integer out of range: {}({}, {})", name, integer, digits)),
+        Value::Float(float) => {
+            let factor = 10_f64.powi(digits.clamp(-308, 308) as i32);
+            let scaled = float * factor;
+            if !scaled.is_finite() {
+                return Ok(Value::Float(float));
+            }
+            Ok(Value::Float(if half_away_from_zero { scaled.round() } else { scaled.trunc() } / factor))
+        }
+        Value::Decimal(decimal) if digits >= 0 => {
+            let scale = u32::try_from(digits).unwrap_or(u32::MAX);
+            match half_away_from_zero {
+                _ if scale >= decimal.scale() => Ok(Value::Decimal(decimal)),
+                true => Ok(Value::Decimal(decimal.rescale(scale)?)),
+                false => Ok(Value::Decimal(decimal.truncate(scale)?)),
+            }
+        }
+        // The fraction cannot move the whole part across a multiple of ten, so it is dropped first.
+        Value::Decimal(decimal) => {
+            let units = round_units(decimal.truncate(0)?.units(), digits.unsigned_abs(), half_away_from_zero)
+                .ok_or_else(|| format!("Decimal out of range: {}({:?}, {})", name, decimal, digits))?;
+            Ok(Value::Decimal(Decimal::new(units, 0)?))
+        }
+        other => Err(format!("{} expects a number, got {}", name, value_type_name(&other))),
+    }
+}
+
+// `units` rounded to a multiple of 10^exponent, None if that overflows.
+fn round_units(units: i128, exponent: u64, half_away_from_zero: bool) -> Option<i128> {
+    // A factor too large for an i128 is more than twice any value, which then rounds to 0.
+    let Some(factor) = u32::try_from(exponent).ok().and_then(|exponent| 10_i128.checked_pow(exponent)) else {
+        return Some(0);
+    };
+    let remainder = units % factor;
+    let step = if half_away_from_zero && remainder.abs() >= factor - remainder.abs() { units.signum() } else { 0 };
+    (units / factor + step).checked_mul(factor)
+}
+
+fn now() -> Result<Timestamp, String> {
+    let micros = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|error| error.to_string())?.as_micros();
+    Ok(Timestamp::from_micros(micros as i64))
+}
+
+// A DATE, TIMESTAMP, TIMESTAMPTZ or text ('now', or a date or timestamp with
+// an optional zone) as a UTC timestamp, None if it is NULL.
+fn timestamp_argument(name: &str, value: Value) -> Result<Option<Timestamp>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Text(text) if text.trim().eq_ignore_ascii_case("now") => now().map(Some),
+        Value::Text(text) => Timestamp::parse_with_zone(&text).map(Some),
+        Value::Date(_) | Value::Timestamp(_) | Value::TimestampTz(_) => match cast(value, &DataType::Timestamp)? {
+            Value::Timestamp(timestamp) => Ok(Some(timestamp)),
+            other => Err(format!("Cannot convert {:?} to TIMESTAMP", other)),
+        },
+        other => Err(format!("{} expects a date, timestamp or text, got {}", name, value_type_name(&other))),
+    }
+}
+
+fn strftime(format: &str, timestamp: Timestamp) -> Result<String, String> {
+    let date = timestamp.date();
+    let (year, month, day) = date.civil();
+    let seconds = timestamp.micros_of_day() / MICROS_PER_SECOND;
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        let field = match chars.next() {
+            Some('Y') => format!("{:04}", year),
+            Some('m') => format!("{:02}", month),
+            Some('d') => format!("{:02}", day),
+            Some('H') => format!("{:02}", seconds / 3600),
+            Some('M') => format!("{:02}", seconds % 3600 / 60),
+            Some('S') => format!("{:02}", seconds % 60),
+            Some('j') => format!("{:03}", date.day_of_year()),
+            Some('w') => date.day_of_week().to_string(),
+            Some('%') => "%".to_string(),
+            Some(other) => return Err(format!("Unknown STRFTIME conversion %{}", other)),
+            None => return Err("STRFTIME format ends with %".to_string()),
+        };
+        formatted.push_str(&field);
+    }
+    Ok(formatted)
+}
+
+// The string functions, where any NULL argument gives NULL.
+fn string_function(name: &str, arguments: &[Value]) -> Result<Value, String> {
+    if arguments.contains(&Value::Null) {
+        return Ok(Value::Null);
+    }
+    let text = |index: usize| match arguments.get(index) {
+        Some(Value::Text(text)) => Ok(text.as_str()),
+        other => Err(format!("{} expects TEXT, got {}", name, other.map_or("nothing", value_type_name))),
+    };
+    let optional_text = |index: usize, default: &'static str| if index < arguments.len() { text(index) } else { Ok(default) };
+    let integer = |index: usize| match arguments.get(index) {
+        Some(Value::Integer(integer)) => Ok(*integer),
+        other => Err(format!("{} expects an INTEGER, got {}", name, other.map_or("nothing", value_type_name))),
+    };
+
+    let result = match name {
+        "TRIM" | "LTRIM" | "RTRIM" => {
+            let characters: Vec<char> = optional_text(1, " ")?.chars().collect();
+            let mut trimmed = text(0)?;
+            if name != "RTRIM" {
+                trimmed = trimmed.trim_start_matches(characters.as_slice());
+            }
+            if name != "LTRIM" {
+                trimmed = trimmed.trim_end_matches(characters.as_slice());
+            }
+            trimmed.to_string()
+        }
+        "REPLACE" => match text(1)? {
+            "" => text(0)?.to_string(),
+            from => text(0)?.replace(from, text(2)?),
+        },
+        "SPLIT_PART" => {
+            let (value, delimiter, n) = (text(0)?, text(1)?, integer(2)?);
+            let fields: Vec<&str> = if delimiter.is_empty() { vec![value] } else { value.split(delimiter).collect() };
+            // A negative n counts back from the last field.
+            let index = match n {
+                0 => return Err("SPLIT_PART field position cannot be 0".to_string()),
+                n if n > 0 => usize::try_from(n - 1).ok(),
+                n => usize::try_from(n.unsigned_abs()).ok().and_then(|back| fields.len().checked_sub(back)),
+            };
+            index.and_then(|index| fields.get(index)).map_or("", |field| field).to_string()
+        }
+        "INSTR" => {
+            let (value, substring) = (text(0)?, text(1)?);
+            let position = value.find(substring).map_or(0, |byte| value[..byte].chars().count() + 1);
+            return Ok(Value::Integer(position as i64));
+        }
+        "LPAD" | "RPAD" => {
+            let (value, length, fill) = (text(0)?, integer(1)?, optional_text(2, " ")?);
+            if length > MAX_PAD_LENGTH {
+                return Err(format!("{} length {} exceeds the maximum of {}", name, length, MAX_PAD_LENGTH));
+            }
+            // Text longer than the length is cut to it, as is text that an empty fill cannot pad.
+            let length = usize::try_from(length).unwrap_or(0);
+            let current = value.chars().count();
+            if current >= length || fill.is_empty() {
+                value.chars().take(length).collect()
+            } else {
+                let padding: String = fill.chars().cycle().take(length - current).collect();
+                if name == "LPAD" { padding + value } else { value.to_string() + &padding }
+            }
+        }
+        _ => unreachable!("{} is not a string function", name),
+    };
+    Ok(Value::Text(result))
+}
+
+// The characters at 1-based positions start .. start + length, so a start
+// before 1 shortens the result rather than shifting it.
+fn substring(text: &str, start: i64, length: Option<i64>) -> String {
+    let first = start.max(1);
+    let skip = usize::try_from(first - 1).unwrap_or(usize::MAX);
+    let take = match length {
+        Some(length) => usize::try_from(start.saturating_add(length).saturating_sub(first)).unwrap_or(0),
+        None => usize::MAX,
+    };
+    text.chars().skip(skip).take(take).collect()
+}
+
+fn map_text(name: &str, value: Value, map: impl Fn(&str) -> String) -> Result<Value, String> {
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Text(text) => Ok(Value::Text(map(&text))),
+        other => Err(format!("{} expects TEXT, got {}", name, value_type_name(&other))),
+    }
+}
+
+fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Integer(_) => "integer",
+        Value::Float(_) => "float",
+        Value::Text(_) => "text",
+        Value::Boolean(_) => "boolean",
+        Value::Date(_) => "date",
+        Value::Timestamp(_) => "timestamp",
+        Value::TimestampTz(_) => "timestamptz",
+        Value::Decimal(_) => "decimal",
+        Value::Blob(_) => "blob",
+    }
+}
+
+// SQL text that reads back as the same value, used to store query results in
+// monitor variables. String literals have no escape for quotes, so text
+// containing one cannot be written back.
+pub fn sql_literal(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Null => Ok("NULL".to_string()),
+        // The tokenizer reads the minus sign separately, and 9223372036854775808 does not fit.
+        Value::Integer(i64::MIN) => Ok(format!("({} - 1)", i64::MIN + 1)),
+        Value::Integer(integer) => Ok(integer.to_string()),
+        Value::Float(float) if float.is_finite() => Ok(format!("{:?}", float)),
+        Value::Float(float) => Err(format!("Float {} has no literal form", float)),
+        Value::Text(text) if text.contains('\'') => Err("Text containing a quote has no literal form".to_string()),
+        Value::Text(text) => Ok(format!("'{}'", text)),
+        Value::Boolean(boolean) => Ok(if *boolean { "TRUE" } else { "FALSE" }.to_string()),
+        Value::Date(date) => Ok(format!("DATE '{:?}'", date)),
+        Value::Timestamp(timestamp) => Ok(format!("TIMESTAMP '{:?}'", timestamp)),
+        Value::TimestampTz(timestamp) => Ok(format!("TIMESTAMPTZ '{:?}Z'", timestamp)),
+        Value::Decimal(decimal) => Ok(format!("DECIMAL '{:?}'", decimal)),
+        Value::Blob(bytes) => Ok(format!("X'{}'", bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<String>())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temporal::Date;
+
+    #[test]
+    fn arithmetic_uses_the_common_type() {
+        assert_eq!(binary(BinaryOperator::Add, Value::Integer(1), Value::Integer(2)), Ok(Value::Integer(3)));
+        assert_eq!(binary(BinaryOperator::Multiply, Value::Integer(2), Value::Float(1.5)), Ok(Value::Float(3.0)));
+        assert_eq!(
+            binary(BinaryOperator::Add, Value::Integer(1), Value::Decimal(Decimal::parse("0.25").unwrap())),
+            Ok(Value::Decimal(Decimal::parse("1.25").unwrap()))
+        );
+        assert_eq!(binary(BinaryOperator::Concat, Value::Text("a".to_string()), Value::Integer(1)), Ok(Value::Text("a1".to_string())));
+        assert_eq!(binary(BinaryOperator::Subtract, Value::Null, Value::Integer(1)), Ok(Value::Null));
+    }
+
+    #[test]
+    fn arithmetic_errors_instead_of_overflowing() {
+        assert!(binary(BinaryOperator::Add, Value::Integer(i64::MAX), Value::Integer(1)).is_err());
+        assert!(binary(BinaryOperator::Divide, Value::Integer(1), Value::Integer(0)).is_err());
+        assert!(binary(BinaryOperator::Divide, Value::Integer(i64::MIN), Value::Integer(-1)).is_err());
+        assert!(binary(BinaryOperator::Add, Value::Integer(1), Value::Text("1".to_string())).is_err());
+    }
+
+    #[test]
+    fn compare_coerces_numbers_and_propagates_null() {
+        assert_eq!(compare(Value::Integer(2), Value::Float(2.0)), Ok(Some(Ordering::Equal)));
+        assert_eq!(compare(Value::Integer(2), Value::Decimal(Decimal::parse("1.50").unwrap())), Ok(Some(Ordering::Greater)));
+        assert_eq!(compare(Value::Null, Value::Integer(1)), Ok(None));
+        assert!(compare(Value::Integer(1), Value::Text("1".to_string())).is_err());
+    }
+
+    #[test]
+    fn literals_read_back_as_sql() {
+        assert_eq!(sql_literal(&Value::Integer(-5)), Ok("-5".to_string()));
+        assert_eq!(sql_literal(&Value::Float(1e30)), Ok("1e30".to_string()));
+        assert_eq!(sql_literal(&Value::Text("bob".to_string())), Ok("'bob'".to_string()));
+        assert_eq!(sql_literal(&Value::Blob(vec![0xAB, 0x01])), Ok("X'AB01'".to_string()));
+        assert!(sql_literal(&Value::Text("it's".to_string())).is_err());
+    }
+
+    fn math(name: &str, arguments: Vec<Value>) -> Result<Value, String> {
+        call(name, arguments, &Session::new())
+    }
+
+    fn decimal(text: &str) -> Value {
+        Value::Decimal(Decimal::parse(text).unwrap())
+    }
+
+    #[test]
+    fn substr_counts_characters_from_one() {
+        let substr = |arguments: Vec<Value>| call("SUBSTR", arguments, &Session::new());
+        let text = |text: &str| Value::Text(text.to_string());
+        assert_eq!(substr(vec![text("héllo"), Value::Integer(2), Value::Integer(3)]), Ok(text("éll")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(3)]), Ok(text("llo")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(0), Value::Integer(2)]), Ok(text("h")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(-2), Value::Integer(2)]), Ok(text("")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(-1)]), Ok(text("hello")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(4), Value::Integer(10)]), Ok(text("lo")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(9)]), Ok(text("")));
+        assert_eq!(substr(vec![text("hello"), Value::Integer(1), Value::Null]), Ok(Value::Null));
+        assert!(substr(vec![text("hello"), Value::Integer(1), Value::Integer(-1)]).is_err());
+        assert!(substr(vec![Value::Integer(5), Value::Integer(1)]).is_err());
+    }
+
+    #[test]
+    fn string_functions_work_on_characters() {
+        let string = |name: &str, arguments: &[&str]| {
+            let arguments = arguments.iter().map(|argument| match argument.parse() {
+                Ok(integer) => Value::Integer(integer),
+                Err(_) => Value::Text(argument.to_string()),
+            });
+            call(name, arguments.collect(), &Session::new())
+        };
+        let text = |text: &str| Ok(Value::Text(text.to_string()));
+        assert_eq!(string("TRIM", &["  hi  "]), text("hi"));
+        assert_eq!(string("LTRIM", &["xxhixx", "x"]), text("hixx"));
+        assert_eq!(string("RTRIM", &["héé", "é"]), text("h"));
+        assert_eq!(string("REPLACE", &["a.b.c", ".", "::"]), text("a::b::c"));
+        assert_eq!(string("REPLACE", &["abc", "", "x"]), text("abc"));
+        assert_eq!(string("SPLIT_PART", &["a,b,,c", ",", "2"]), text("b"));
+        assert_eq!(string("SPLIT_PART", &["a,b,,c", ",", "-1"]), text("c"));
+        assert_eq!(string("SPLIT_PART", &["a,b", ",", "5"]), text(""));
+        assert!(string("SPLIT_PART", &["a,b", ",", "0"]).is_err());
+        assert_eq!(string("INSTR", &["naïve café", "café"]), Ok(Value::Integer(7)));
+        assert_eq!(string("INSTR", &["abc", "z"]), Ok(Value::Integer(0)));
+        assert_eq!(string("LPAD", &["ab", "4", "*"]), text("**ab"));
+        assert_eq!(string("RPAD", &["é", "4", "ab"]), text("éaba"));
+        assert_eq!(string("LPAD", &["hello", "2"]), text("he"));
+        assert_eq!(string("RPAD", &["hi", "-1"]), text(""));
+        assert_eq!(call("TRIM", vec![Value::Text("a".to_string()), Value::Null], &Session::new()), Ok(Value::Null));
+        assert!(string("LPAD", &["a", "100000000"]).is_err());
+    }
+
+    #[test]
+    fn date_functions_apply_modifiers_in_order() {
+        let date = |arguments: &[&str]| {
+            let arguments = arguments.iter().map(|argument| Value::Text(argument.to_string())).collect();
+            call("DATE", arguments, &Session::new())
+        };
+        let day = |text: &str| Ok(Value::Date(Date::parse(text).unwrap()));
+        assert_eq!(date(&["2024-03-10 23:30:00", "-7 days"]), day("2024-03-03"));
+        assert_eq!(date(&["2024-01-31", "+1 month"]), day("2024-02-29"));
+        assert_eq!(date(&["2024-02-29", "+1 year"]), day("2025-02-28"));
+        assert_eq!(date(&["2024-05-17", "start of month", "-1 day"]), day("2024-04-30"));
+        assert_eq!(date(&["2024-05-17 22:00:00+02:00", "+5 hours"]), day("2024-05-18"));
+        assert!(date(&["2024-05-17", "+1 fortnight"]).is_err());
+        assert!(date(&["9999-12-31", "+1 day"]).is_err());
+        assert_eq!(call("DATE", vec![Value::Text("now".to_string()), Value::Null], &Session::new()), Ok(Value::Null));
+    }
+
+    #[test]
+    fn date_parts_and_strftime_read_the_calendar() {
+        let timestamp = Value::Timestamp(Timestamp::parse("2024-12-31 08:05:09").unwrap());
+        let session = Session::new();
+        let part = |name: &str| call("DATE_PART", vec![Value::Text(name.to_string()), timestamp.clone()], &session);
+        assert_eq!(part("year"), Ok(Value::Integer(2024)));
+        assert_eq!(part("minute"), Ok(Value::Integer(5)));
+        assert_eq!(part("dow"), Ok(Value::Integer(2)));
+        assert!(part("week").is_err());
+        let strftime = |format: &str, modifier: &str| {
+            let arguments = vec![Value::Text(format.to_string()), timestamp.clone(), Value::Text(modifier.to_string())];
+            call("STRFTIME", arguments, &session)
+        };
+        assert_eq!(strftime("%Y-%m-%d %H:%M:%S %j %w %%", "+0 days"), Ok(Value::Text("2024-12-31 08:05:09 366 2 %".to_string())));
+        assert_eq!(strftime("%j", "+1 day"), Ok(Value::Text("001".to_string())));
+        assert!(strftime("%q", "+0 days").is_err());
+    }
+
+    #[test]
+    fn math_functions_keep_exact_types_where_they_can() {
+        assert_eq!(math("FLOOR", vec![Value::Float(-1.5)]), Ok(Value::Float(-2.0)));
+        assert_eq!(math("CEIL", vec![Value::Float(-1.5)]), Ok(Value::Float(-1.0)));
+        assert_eq!(math("FLOOR", vec![decimal("-1.25")]), Ok(decimal("-2")));
+        assert_eq!(math("CEIL", vec![decimal("1.25")]), Ok(decimal("2")));
+        assert_eq!(math("CEIL", vec![Value::Integer(7)]), Ok(Value::Integer(7)));
+        assert_eq!(math("SIGN", vec![decimal("-0.5")]), Ok(Value::Integer(-1)));
+        assert_eq!(math("TRUNC", vec![decimal("-1.279"), Value::Integer(2)]), Ok(decimal("-1.27")));
+        assert_eq!(math("TRUNC", vec![Value::Integer(1999), Value::Integer(-2)]), Ok(Value::Integer(1900)));
+        assert_eq!(math("TRUNC", vec![Value::Float(-2.7)]), Ok(Value::Float(-2.0)));
+        assert_eq!(math("TRUNC", vec![Value::Float(1.5), Value::Null]), Ok(Value::Null));
+    }
+
+    #[test]
+    fn round_goes_half_away_from_zero() {
+        assert_eq!(math("ROUND", vec![Value::Float(2.5)]), Ok(Value::Float(3.0)));
+        assert_eq!(math("ROUND", vec![Value::Float(-2.5)]), Ok(Value::Float(-3.0)));
+        assert_eq!(math("ROUND", vec![Value::Float(1.2345), Value::Integer(2)]), Ok(Value::Float(1.23)));
+        assert_eq!(math("ROUND", vec![decimal("1.245"), Value::Integer(2)]), Ok(decimal("1.25")));
+        assert_eq!(math("ROUND", vec![decimal("-1.245"), Value::Integer(2)]), Ok(decimal("-1.25")));
+        assert_eq!(math("ROUND", vec![decimal("-14.9"), Value::Integer(-1)]), Ok(decimal("-10")));
+        assert_eq!(math("ROUND", vec![Value::Integer(1250), Value::Integer(-2)]), Ok(Value::Integer(1300)));
+        assert_eq!(math("ROUND", vec![Value::Integer(-1250), Value::Integer(-2)]), Ok(Value::Integer(-1300)));
+        assert_eq!(math("ROUND", vec![Value::Integer(7), Value::Integer(-40)]), Ok(Value::Integer(0)));
+        assert!(math("ROUND", vec![Value::Integer(i64::MAX), Value::Integer(-1)]).is_err());
+    }
+
+    #[test]
+    fn mod_keeps_the_sign_of_the_dividend() {
+        assert_eq!(math("MOD", vec![Value::Integer(-7), Value::Integer(3)]), Ok(Value::Integer(-1)));
+        assert_eq!(math("MOD", vec![Value::Integer(i64::MIN), Value::Integer(-1)]), Ok(Value::Integer(0)));
+        assert_eq!(math("MOD", vec![Value::Float(7.5), Value::Integer(2)]), Ok(Value::Float(1.5)));
+        assert_eq!(math("MOD", vec![decimal("-7.5"), Value::Integer(2)]), Ok(Value::Float(-1.5)));
+        assert_eq!(math("MOD", vec![Value::Null, Value::Integer(0)]), Ok(Value::Null));
+    }
+
+    #[test]
+    fn math_functions_return_floats() {
+        assert_eq!(math("POWER", vec![Value::Integer(2), Value::Integer(10)]), Ok(Value::Float(1024.0)));
+        assert_eq!(math("SQRT", vec![decimal("2.25")]), Ok(Value::Float(1.5)));
+        assert_eq!(math("LOG", vec![Value::Integer(1000)]), Ok(Value::Float(3.0)));
+        assert_eq!(math("LOG", vec![Value::Integer(2), Value::Integer(8)]), Ok(Value::Float(3.0)));
+        assert_eq!(math("EXP", vec![Value::Integer(0)]), Ok(Value::Float(1.0)));
+        assert_eq!(math("SQRT", vec![Value::Null]), Ok(Value::Null));
+        assert!(math("EXP", vec![Value::Integer(1000)]).is_err());
+        assert!(math("SQRT", vec![Value::Text("4".to_string())]).is_err());
+    }
+
+    #[test]
+    fn domain_errors_follow_the_session_setting() {
+        let calls = [
+            ("SQRT", vec![Value::Integer(-1)]),
+            ("LOG", vec![Value::Integer(0)]),
+            ("LOG", vec![Value::Integer(1), Value::Integer(5)]),
+            ("POWER", vec![Value::Integer(0), Value::Integer(-1)]),
+            ("POWER", vec![Value::Integer(-8), Value::Float(0.5)]),
+            ("MOD", vec![Value::Integer(5), Value::Integer(0)]),
+            ("MOD", vec![Value::Float(5.0), decimal("0.0")]),
+        ];
+        let mut session = Session::new();
+        for (name, arguments) in &calls {
+            assert!(call(name, arguments.clone(), &session).is_err(), "{}", name);
+        }
+        session.set("math_domain_errors", Value::Text("null".to_string())).unwrap();
+        for (name, arguments) in calls {
+            assert_eq!(call(name, arguments, &session), Ok(Value::Null), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+#[allow(dead_code)]
+pub struct ScalarFunction {
+    pub name: &'static str,
+    pub min_args: usize,
+    // None means the function is variadic.
+    pub max_args: Option<usize>,
+    pub description: &'static str,
+}
+
+pub const SCALAR_FUNCTIONS: &[ScalarFunction] = &[
+    // Strings; positions and lengths count characters, not bytes
+    ScalarFunction { name: "UPPER", min_args: 1, max_args: Some(1), description: "UPPER(text) - text converted to upper case" },
+    ScalarFunction { name: "LOWER", min_args: 1, max_args: Some(1), description: "LOWER(text) - text converted to lower case" },
+    ScalarFunction { name: "LENGTH", min_args: 1, max_args: Some(1), description: "LENGTH(x) - number of characters in text, or bytes in a blob" },
+    ScalarFunction { name: "SUBSTR", min_args: 2, max_args: Some(3), description: "SUBSTR(text, start[, length]) - length characters of text from the 1-based start (default: to the end)" },
+    ScalarFunction { name: "TRIM", min_args: 1, max_args: Some(2), description: "TRIM(text[, characters]) - text without leading and trailing characters (default: spaces)" },
+    ScalarFunction { name: "LTRIM", min_args: 1, max_args: Some(2), description: "LTRIM(text[, characters]) - text without leading characters (default: spaces)" },
+    ScalarFunction { name: "RTRIM", min_args: 1, max_args: Some(2), description: "RTRIM(text[, characters]) - text without trailing characters (default: spaces)" },
+    ScalarFunction { name: "REPLACE", min_args: 3, max_args: Some(3), description: "REPLACE(text, from, to) - text with every occurrence of from replaced by to" },
+    ScalarFunction { name: "SPLIT_PART", min_args: 3, max_args: Some(3), description: "SPLIT_PART(text, delimiter, n) - n-th field (1-based) of text split on delimiter" },
+    ScalarFunction { name: "INSTR", min_args: 2, max_args: Some(2), description: "INSTR(text, substring) - 1-based position of substring in text, 0 if absent" },
+    ScalarFunction { name: "LPAD", min_args: 2, max_args: Some(3), description: "LPAD(text, length[, fill]) - text left-padded with fill (default: space) to length" },
+    ScalarFunction { name: "RPAD", min_args: 2, max_args: Some(3), description: "RPAD(text, length[, fill]) - text right-padded with fill (default: space) to length" },
+    // Math; NULL arguments give NULL, domain errors follow the math_domain_errors setting
+    ScalarFunction { name: "ABS", min_args: 1, max_args: Some(1), description: "ABS(x) - absolute value of x, of the same type as x" },
+    ScalarFunction { name: "ROUND", min_args: 1, max_args: Some(2), description: "ROUND(x[, digits]) - x rounded half away from zero to digits decimal places (default 0), of the same type as x" },
+    ScalarFunction { name: "MOD", min_args: 2, max_args: Some(2), description: "MOD(x, y) - remainder of x / y with the sign of x; INTEGER if both are integers, otherwise FLOAT; y = 0 is a domain error" },
+    ScalarFunction { name: "FLOOR", min_args: 1, max_args: Some(1), description: "FLOOR(x) - largest integer not greater than x" },
+    ScalarFunction { name: "CEIL", min_args: 1, max_args: Some(1), description: "CEIL(x) - smallest integer not less than x" },
+    ScalarFunction { name: "POWER", min_args: 2, max_args: Some(2), description: "POWER(x, y) - x raised to the power y" },
+    ScalarFunction { name: "SQRT", min_args: 1, max_args: Some(1), description: "SQRT(x) - square root of x; x < 0 is a domain error" },
+    ScalarFunction { name: "LOG", min_args: 1, max_args: Some(2), description: "LOG([base, ]x) - logarithm of x (default base 10); x <= 0 is a domain error" },
+    ScalarFunction { name: "EXP", min_args: 1, max_args: Some(1), description: "EXP(x) - e raised to the power x" },
+    ScalarFunction { name: "SIGN", min_args: 1, max_args: Some(1), description: "SIGN(x) - -1, 0 or 1 depending on the sign of x" },
+    ScalarFunction { name: "TRUNC", min_args: 1, max_args: Some(2), description: "TRUNC(x[, digits]) - x truncated toward zero to digits decimal places (default 0)" },
+    // Date and time; 'now' is the start of the current statement, in UTC
+    ScalarFunction { name: "NOW", min_args: 0, max_args: Some(0), description: "NOW() - current timestamp with time zone" },
+    ScalarFunction { name: "DATE", min_args: 1, max_args: None, description: "DATE(value, modifier, ...) - date of a date, timestamp, text or 'now', shifted by modifiers such as '-7 days' or '+1 month'" },
+    ScalarFunction { name: "DATE_PART", min_args: 2, max_args: Some(2), description: "DATE_PART(part, value) - year, month, day, hour, minute, second or dow of a date or timestamp, as an INTEGER" },
+    ScalarFunction { name: "STRFTIME", min_args: 2, max_args: None, description: "STRFTIME(format, value, modifier, ...) - value formatted with %Y %m %d %H %M %S %j %w %%, after applying modifiers as in DATE" },
+    // Conditional
+    ScalarFunction { name: "IIF", min_args: 3, max_args: Some(3), description: "IIF(condition, then, else) - then if condition is true, otherwise else (including NULL)" },
+    ScalarFunction { name: "GREATEST", min_args: 1, max_args: None, description: "GREATEST(x, ...) - largest non-NULL argument, NULL if all are NULL" },
+    ScalarFunction { name: "LEAST", min_args: 1, max_args: None, description: "LEAST(x, ...) - smallest non-NULL argument, NULL if all are NULL" },
+    // NULL handling
+    ScalarFunction { name: "COALESCE", min_args: 1, max_args: None, description: "COALESCE(x, ...) - first non-NULL argument, NULL if all are NULL; later arguments are not evaluated" },
+    ScalarFunction { name: "IFNULL", min_args: 2, max_args: Some(2), description: "IFNULL(x, fallback) - x unless it is NULL, otherwise fallback" },
+    ScalarFunction { name: "NULLIF", min_args: 2, max_args: Some(2), description: "NULLIF(x, y) - NULL if x equals y, otherwise x" },
+    // Introspection
+    ScalarFunction { name: "TYPEOF", min_args: 1, max_args: Some(1), description: "TYPEOF(x) - name of the runtime type of x, e.g. 'integer' or 'null'" },
+    // Hashing and encoding; text is hashed as its UTF-8 bytes
+    ScalarFunction { name: "MD5", min_args: 1, max_args: Some(1), description: "MD5(x) - MD5 digest of text or blob x as lowercase hex" },
+    ScalarFunction { name: "SHA256", min_args: 1, max_args: Some(1), description: "SHA256(x) - SHA-256 digest of text or blob x as lowercase hex" },
+    ScalarFunction { name: "HEX", min_args: 1, max_args: Some(1), description: "HEX(x) - uppercase hexadecimal encoding of text or blob x" },
+    ScalarFunction { name: "BASE64", min_args: 1, max_args: Some(1), description: "BASE64(x) - standard padded base64 encoding of text or blob x" },
+];
+
+#[allow(dead_code)]
+pub struct TableFunction {
+    pub name: &'static str,
+    pub arguments: usize,
+    pub columns: &'static [&'static str],
+    pub description: &'static str,
+}
+
+pub const TABLE_FUNCTIONS: &[TableFunction] = &[
+    TableFunction { name: "TABLE_INFO", arguments: 1, columns: &["name", "type", "nullable", "default"], description: "table_info(table) - one row per column of table" },
+    TableFunction { name: "JOBS", arguments: 0, columns: &["name", "schedule", "last_run", "next_run", "status"], description: "jobs() - one row per scheduled job with its last outcome" },
+];
+
+pub fn lookup_table_function(name: &str) -> Option<&'static TableFunction> {
+    TABLE_FUNCTIONS
+        .iter()
+        .find(|function| function.name.eq_ignore_ascii_case(name))
+}
+
+pub fn lookup(name: &str) -> Option<&'static ScalarFunction> {
+    SCALAR_FUNCTIONS
+        .iter()
+        .find(|function| function.name.eq_ignore_ascii_case(name))
+}
+
+impl ScalarFunction {
+    pub fn check_arity(&self, count: usize) -> Result<(), String> {
+        let too_many = self.max_args.is_some_and(|max| count > max);
+        if count >= self.min_args && !too_many {
+            return Ok(());
+        }
+
+        let expected = match self.max_args {
+            Some(max) if max == self.min_args => format!("{}", max),
+            Some(max) => format!("{} to {}", self.min_args, max),
+            None => format!("at least {}", self.min_args),
+        };
+        Err(format!("{} expects {} argument(s), got {}", self.name, expected, count))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    pub const ALL: [AggregateFunction; 5] = [
+        AggregateFunction::Count,
+        AggregateFunction::Sum,
+        AggregateFunction::Avg,
+        AggregateFunction::Min,
+        AggregateFunction::Max,
+    ];
+
+    pub fn lookup(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "COUNT" => Some(AggregateFunction::Count),
+            "SUM" => Some(AggregateFunction::Sum),
+            "AVG" => Some(AggregateFunction::Avg),
+            "MIN" => Some(AggregateFunction::Min),
+            "MAX" => Some(AggregateFunction::Max),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "COUNT(*) | COUNT([DISTINCT] x) - number of rows, or of non-NULL values of x",
+            AggregateFunction::Sum => "SUM([DISTINCT] x) - sum of the non-NULL values of x",
+            AggregateFunction::Avg => "AVG([DISTINCT] x) - average of the non-NULL values of x",
+            AggregateFunction::Min => "MIN(x) - smallest non-NULL value of x",
+            AggregateFunction::Max => "MAX(x) - largest non-NULL value of x",
+        }
+    }
+}
+
+// Functions that are only valid as window functions, i.e. with an OVER clause.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RankingFunction {
+    RowNumber,
+    Rank,
+}
+
+impl RankingFunction {
+    pub const ALL: [RankingFunction; 2] = [RankingFunction::RowNumber, RankingFunction::Rank];
+
+    pub fn lookup(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "ROW_NUMBER" => Some(RankingFunction::RowNumber),
+            "RANK" => Some(RankingFunction::Rank),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RankingFunction::RowNumber => "ROW_NUMBER",
+            RankingFunction::Rank => "RANK",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            RankingFunction::RowNumber => "ROW_NUMBER() OVER (...) - 1-based position of the row in its partition",
+            RankingFunction::Rank => "RANK() OVER (...) - rank of the row in its partition, with gaps after ties",
+        }
+    }
+}
+
+// Listing for `.help functions`, built from the registries above.
+pub fn print_reference() {
+    println!("Scalar functions:");
+    for function in SCALAR_FUNCTIONS {
+        println!("  {}", function.description);
+    }
+    println!("Aggregate functions (also usable as window functions with OVER):");
+    for function in AggregateFunction::ALL {
+        println!("  {}", function.description());
+    }
+    println!("Window functions:");
+    for function in RankingFunction::ALL {
+        println!("  {}", function.description());
+    }
+    println!("Table functions (used in FROM or with PRAGMA):");
+    for function in TABLE_FUNCTIONS {
+        println!("  {}", function.description);
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::{stdin, stdout, IsTerminal, Write};
+
+// Terminals that support bracketed paste wrap pasted text in these markers.
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+pub fn set_bracketed_paste(enabled: bool) {
+    if stdin().is_terminal() && stdout().is_terminal() {
+        print!("{}", if enabled { "\x1b[?2004h" } else { "\x1b[?2004l" });
+        stdout().flush().unwrap();
+    }
+}
+
+// Tracks whether the lines being read are part of a paste, so the monitor
+// can hold back its prompts until the whole paste has been read.
+pub struct PasteState {
+    pasting: bool,
+}
+
+impl PasteState {
+    pub fn new() -> Self {
+        PasteState { pasting: false }
+    }
+
+    pub fn is_pasting(&self) -> bool {
+        self.pasting
+    }
+
+    // Removes the paste markers from a line read from the terminal.
+    pub fn strip_markers(&mut self, line: &str) -> String {
+        if line.contains(PASTE_START) {
+            self.pasting = true;
+        }
+        if line.contains(PASTE_END) {
+            self.pasting = false;
+        }
+        line.replace(PASTE_START, "").replace(PASTE_END, "")
+    }
+}
+
+// Takes every complete statement, i.e. one ending with ';' outside a string
+// literal, off the front of the buffer. An unfinished statement stays behind.
+pub fn split_statements(buffer: &mut String) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+
+    for (index, c) in buffer.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == ';' => {
+                let statement = buffer[start..=index].trim();
+                if statement != ";" {
+                    statements.push(statement.to_string());
+                }
+                start = index + 1;
+            }
+            None => {}
+        }
+    }
+
+    *buffer = buffer[start..].trim_start().to_string();
+    statements
+}
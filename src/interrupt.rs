@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// Catches Ctrl-C while alive, so a command that runs until interrupted, like
+// .watch, can stop without ending the monitor. Dropping it restores the
+// default, where Ctrl-C ends the monitor.
+pub struct Interrupt {
+    _private: (),
+}
+
+impl Interrupt {
+    pub fn catch() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        platform::set_handler(true);
+        Interrupt { _private: () }
+    }
+
+    pub fn is_set(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        platform::set_handler(false);
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: i32 = 2;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+    }
+
+    // Only an atomic store, which is safe inside a signal handler.
+    extern "C" fn on_interrupt(_signum: i32) {
+        super::INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn set_handler(catch: bool) {
+        let handler = if catch { on_interrupt as extern "C" fn(i32) as usize } else { SIG_DFL };
+        unsafe {
+            signal(SIGINT, handler);
+        }
+    }
+}
+
+// Elsewhere Ctrl-C keeps ending the monitor, .watch included.
+#[cfg(not(unix))]
+mod platform {
+    pub fn set_handler(_catch: bool) {}
+}
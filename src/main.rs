@@ -1,8 +1,37 @@
 use std::io::{stdin, stdout, Write};
+use std::thread;
+use std::time::{Duration, Instant};
 mod tokenizer;
 use crate::tokenizer::*;
 mod parser;
 use crate::parser::*;
+mod profiler;
+use crate::profiler::*;
+mod row;
+mod temporal;
+mod decimal;
+mod coercion;
+mod session;
+mod pattern;
+mod functions;
+mod schedule;
+mod variables;
+mod script;
+mod output;
+mod input;
+mod capture;
+mod evaluate;
+mod digest;
+mod interrupt;
+use crate::session::*;
+use crate::variables::Variables;
+use crate::script::ScriptBlocks;
+use crate::output::{copy_to_clipboard, format_value, markdown_table};
+use crate::capture::Capture;
+use crate::interrupt::Interrupt;
+use crate::input::{set_bracketed_paste, split_statements, PasteState};
+
+const WATCH_POLL: Duration = Duration::from_millis(100);
 
 fn main() -> Result<(), String>{
     println!("Welcome to the PlecakDB monitor");
@@ -11,58 +40,356 @@ fn main() -> Result<(), String>{
 
     let mut multiline_buffer = String::new();
     let mut command_log: Vec<String> = Vec::new();
+    let mut profiler = Profiler::new();
+    let mut session = Session::new();
+    let mut variables = Variables::new();
+    let mut blocks = ScriptBlocks::new();
+    let mut timer = false;
+    let mut capture: Option<Capture> = None;
+    let mut paste = PasteState::new();
+    set_bracketed_paste(true);
     loop {
-        if multiline_buffer.is_empty() {
-            print!("PlecakDB [(dbname)]> ");
-        } else {
-            print!("...>  ");
+        // Prompts would interleave with the pasted lines, so they wait for the paste to end.
+        if !paste.is_pasting() {
+            if multiline_buffer.is_empty() {
+                print!("PlecakDB [(dbname)]> ");
+            } else {
+                print!("...>  ");
+            }
         }
-        let _ = stdout().flush().unwrap();
+        stdout().flush().unwrap();
 
         let mut input = String::new();
-        stdin().read_line(&mut input).expect("Input error");
+        // End of input, e.g. the end of a script piped into the monitor.
+        if stdin().read_line(&mut input).expect("Input error") == 0 {
+            println!();
+            set_bracketed_paste(false);
+            break Ok(());
+        }
+        let input = paste.strip_markers(&input);
         let input = input.trim();
         if input.is_empty() {
             continue;
         }
 
+        if multiline_buffer.is_empty() {
+            if let Some(result) = blocks.handle(input, &variables) {
+                if let Err(error) = result {
+                    println!("Error: {}", error);
+                }
+                continue;
+            }
+        }
+        if !blocks.is_active() {
+            continue;
+        }
+        if input.starts_with("--") && multiline_buffer.is_empty() {
+            continue;
+        }
+
         if input.starts_with('.') && multiline_buffer.is_empty() {
             match input {
                 ".exit" => {
                     println!("Goodbye!");
+                    set_bracketed_paste(false);
                     break Ok(());
                 }
                 ".help" => {
                     println!("Available commands:");
                     println!("  .exit      - Exit the REPL");
+                    println!("  .help keywords|functions - List reserved words or built-in functions");
                     println!("  .history   - Show history of commands");
+                    println!("  .mode debug|pretty|markdown - Choose how results are printed");
+                    println!("  .output stdout|clipboard - Send results to the terminal or the clipboard");
+                    println!("  .profile on|off|report|reset - Profile statement timings");
+                    println!("  .timer on|off - Show how long each phase of a statement took");
+                    println!("  .capture FILE|off - Record statements and timings to a script for replay");
+                    println!("  -- text    - A comment line, ignored");
+                    println!("  .watch N SELECT ...; - Re-run a SELECT without FROM every N seconds until Ctrl-C");
+                    println!("  .settings  - Show session settings (change them with SET name = value;)");
+                    println!("  .set name = value|SELECT ... - Define a variable, used as :name in statements");
+                    println!("  .unset name - Remove a variable");
+                    println!("  .vars      - Show variables");
+                    println!("  .if value | .if a == b | .if a != b, .else, .endif - Run input conditionally");
                     println!("  All other inputs are treated as SQL commands.");
                 }
+                ".help keywords" => {
+                    let mut keywords = KEYWORDS.to_vec();
+                    keywords.extend(["TRUE", "FALSE"]);
+                    keywords.sort_unstable();
+                    for line in keywords.chunks(8) {
+                        println!("  {}", line.join(" "));
+                    }
+                }
+                ".help functions" => {
+                    functions::print_reference();
+                }
                 ".history" => {
                     for i in (0..command_log.len()).rev() {
                         println!("{}.  {}", i + 1, command_log[i]);
                     }
                 }
+                ".settings" => {
+                    session.print_settings();
+                }
+                ".profile on" => {
+                    profiler.set_enabled(true);
+                    println!("Profiling enabled");
+                }
+                ".profile off" => {
+                    profiler.set_enabled(false);
+                    println!("Profiling disabled");
+                }
+                ".profile report" => {
+                    profiler.report();
+                }
+                ".profile reset" => {
+                    profiler.reset();
+                    println!("Profile data cleared");
+                }
+                ".timer on" => {
+                    timer = true;
+                    println!("Timer enabled");
+                }
+                ".timer off" => {
+                    timer = false;
+                    println!("Timer disabled");
+                }
+                ".capture off" => match capture.take() {
+                    Some(finished) => println!("Capture saved to {}", finished.path()),
+                    None => println!("Error: No capture in progress"),
+                },
+                _ if input.starts_with(".capture ") => match session.check_dot_command(input).and_then(|()| Capture::start(input[9..].trim())) {
+                    Ok(started) => {
+                        println!("Capturing statements to {}", started.path());
+                        capture = Some(started);
+                    }
+                    Err(error) => println!("Error: {}", error),
+                },
+                ".vars" => {
+                    variables.print(session.output_format() == "markdown");
+                }
+                _ if input.starts_with(".mode ") => {
+                    if let Err(error) = session.set("output_format", Value::Text(input[6..].trim().to_string())) {
+                        println!("Error: {}", error);
+                    }
+                }
+                _ if input.starts_with(".output ") => {
+                    if let Err(error) = session.set("output_target", Value::Text(input[8..].trim().to_string())) {
+                        println!("Error: {}", error);
+                    }
+                }
+                _ if input.starts_with(".watch ") => {
+                    if let Err(error) = variables.interpolate(&input[7..]).and_then(|watched| watch(&watched, &session)) {
+                        println!("Error: {}", error);
+                    }
+                }
+                _ if input.starts_with(".set ") => match variables.assign(&input[5..], &session) {
+                    Ok(name) => println!(":{} set", name),
+                    Err(error) => println!("Error: {}", error),
+                },
+                _ if input.starts_with(".unset ") => {
+                    let name = input[7..].trim();
+                    if !variables.unset(name) {
+                        println!("Error: Undefined variable :{}", name);
+                    }
+                }
                 _ => {
                     println!("Wrong command!");
                 }
             }
             continue;
         }
-        if !input.ends_with(';') {
-            multiline_buffer.push_str(input);
-            multiline_buffer.push(' ');
-            continue;
-        } else {
-            multiline_buffer.push_str(input);
+        multiline_buffer.push_str(input);
+        multiline_buffer.push(' ');
+        // A single line, e.g. a paste, may hold several statements; they run in order.
+        for command in split_statements(&mut multiline_buffer) {
+            let command = match variables.interpolate(&command) {
+                Ok(command) => command,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    continue;
+                }
+            };
+            let mut timing = StatementTiming::start();
+            // A bad statement only skips itself, so the rest of a paste still runs.
+            let mut tokenizer = Tokenizer::new(command.as_str());
+            let tokens = match tokenizer.tokenize() {
+                Ok(tokens) => tokens,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    continue;
+                }
+            };
+            timing.phase("tokenize");
+            let mut parser = Parser::new(tokens);
+            let query = match parser.parse() {
+                Ok(query) => query,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    continue;
+                }
+            };
+            timing.phase("parse");
+            command_log.push(command.clone());
+
+            if let Query::Set(set) = &query {
+                match session.set(set.name(), set.value().clone()) {
+                    Ok(()) => println!("{} set", set.name()),
+                    Err(error) => println!("Error: {}", error),
+                }
+                timing.phase("execute");
+                profiler.record(tokens, query.kind(), timing.total());
+                profiler.record_phases(&timing);
+                if timer {
+                    println!("{}", timing);
+                }
+                if let Some(Err(error)) = capture.as_mut().map(|capture| capture.record(&command, &timing)) {
+                    println!("Error: {}", error);
+                }
+                continue;
+            }
+            if session.sandbox() {
+                if let Err(error) = query.check_sandbox() {
+                    println!("Error: {}", error);
+                    continue;
+                }
+            }
+            if session.safe_updates() && query.is_unrestricted_write() {
+                println!("Error: {} without WHERE is refused while safe_updates is on", query.kind());
+                continue;
+            }
+
+            if session.warn_null_comparison() && query.compares_with_null() {
+                println!("Warning: '= NULL' and '!= NULL' are never true, use IS [NOT] NULL instead");
+            }
+            timing.phase("check");
+
+            let rendered = match execute(&query, &session) {
+                Ok(rendered) => rendered,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    continue;
+                }
+            };
+            if session.output_target() == "clipboard" {
+                match copy_to_clipboard(&rendered) {
+                    Ok(()) => println!("Result copied to clipboard"),
+                    Err(error) => println!("Error: {}", error),
+                }
+            } else {
+                println!("{}", rendered);
+            }
+            timing.phase("output");
+            profiler.record(tokens, query.kind(), timing.total());
+            profiler.record_phases(&timing);
+            if timer {
+                println!("{}", timing);
+            }
+            if let Some(Err(error)) = capture.as_mut().map(|capture| capture.record(&command, &timing)) {
+                println!("Error: {}", error);
+            }
+        }
+    }
+}
+
+// Output of a statement: the rows of a SELECT without FROM, which needs no
+// tables, and the parsed statement otherwise.
+fn execute(query: &Query, session: &Session) -> Result<String, String> {
+    match query {
+        Query::Select(select) if select.is_constant() => {
+            Ok(render_rows(&select.column_names(), &select.evaluate_constant(session)?, session))
+        }
+        _ => Ok(render(query, session)),
+    }
+}
+
+fn render(query: &Query, session: &Session) -> String {
+    match session.output_format() {
+        "pretty" => format!("{:#?}", query),
+        "markdown" => format!("```\n{:#?}\n```", query),
+        _ => format!("{:?}", query),
+    }
+}
+
+// Result rows of a query; only a SELECT without FROM produces them for now.
+fn render_rows(columns: &[String], rows: &[Vec<Value>], session: &Session) -> String {
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| row.iter().map(format_value).collect()).collect();
+    if session.output_format() == "markdown" {
+        let headers: Vec<&str> = columns.iter().map(String::as_str).collect();
+        return markdown_table(&headers, &cells).trim_end().to_string();
+    }
+
+    let mut widths: Vec<usize> = columns.iter().map(|column| column.chars().count()).collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |row: &[String]| {
+        let padded: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        padded.join(" | ").trim_end().to_string()
+    };
+    let mut lines = vec![line(columns)];
+    lines.push(widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join("-+-"));
+    lines.extend(cells.iter().map(|row| line(row)));
+    lines.push(format!("({} row{})", rows.len(), if rows.len() == 1 { "" } else { "s" }));
+    lines.join("\n")
+}
+
+// Argument of .watch, e.g. "2 SELECT NOW();". The statement runs again on every
+// tick until Ctrl-C, which only ends the watch.
+fn watch(argument: &str, session: &Session) -> Result<(), String> {
+    let usage = || "Usage: .watch seconds SELECT ...;".to_string();
+    let (interval, statement) = argument.trim().split_once(' ').ok_or_else(usage)?;
+    let interval: u64 = interval.parse().map_err(|_| usage())?;
+    if interval == 0 {
+        return Err("Watch interval must be at least 1 second".to_string());
+    }
+    let statement = statement.trim();
+    if !statement.ends_with(';') {
+        return Err(usage());
+    }
+
+    let interval = Duration::from_secs(interval);
+    let interrupt = Interrupt::catch();
+    let mut ticks = 0;
+    while !interrupt.is_set() {
+        let started = Instant::now();
+        let output = match run_watched(statement, session) {
+            Ok(output) => output,
+            // A statement that fails on its first run is reported at once;
+            // a later failure is shown in place of the result and the watch goes on.
+            Err(error) if ticks == 0 => return Err(error),
+            Err(error) => format!("Error: {}", error),
+        };
+        ticks += 1;
+
+        // Clear the screen and move the cursor home.
+        print!("\x1b[2J\x1b[H");
+        println!("Every {}s: {}  (Ctrl-C to stop)", interval.as_secs(), statement);
+        println!();
+        println!("{}", output);
+        stdout().flush().unwrap();
+        // Short naps, so Ctrl-C is noticed well before the next tick.
+        while !interrupt.is_set() && started.elapsed() < interval {
+            thread::sleep(WATCH_POLL.min(interval.saturating_sub(started.elapsed())));
         }
-        let command = multiline_buffer.trim().to_string();
-        multiline_buffer.clear();
-        let mut tokenizer = Tokenizer::new(command.as_str());
-        let tokens = tokenizer.tokenize()?;
-        let mut parser = Parser::new(tokens);
-        let query = parser.parse()?;
-        println!("{:?}", query);
-        command_log.push(command);
     }
-}
\ No newline at end of file
+    println!();
+    Ok(())
+}
+
+fn run_watched(statement: &str, session: &Session) -> Result<String, String> {
+    let mut tokenizer = Tokenizer::new(statement);
+    let tokens = tokenizer.tokenize()?;
+    let mut parser = Parser::new(tokens);
+    let query = parser.parse()?;
+    if !matches!(&query, Query::Select(select) if select.is_constant()) {
+        return Err("Only a SELECT without FROM can be watched until queries read tables".to_string());
+    }
+    if session.sandbox() {
+        query.check_sandbox()?;
+    }
+    execute(&query, session)
+}
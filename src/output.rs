@@ -0,0 +1,59 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::parser::Value;
+
+// Tried in order; the first one that runs successfully wins.
+const CLIPBOARD_COMMANDS: [&[&str]; 4] = [
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["clip.exe"],
+];
+
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    for command in CLIPBOARD_COMMANDS {
+        let child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        if child.wait().is_ok_and(|status| status.success()) && written {
+            return Ok(());
+        }
+    }
+    Err("No clipboard available (tried pbcopy, wl-copy, xclip and clip.exe)".to_string())
+}
+
+// A result cell as shown to the user; text is printed without quotes.
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer(integer) => integer.to_string(),
+        Value::Text(text) => text.clone(),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::TimestampTz(timestamp) => format!("{:?}Z", timestamp),
+        Value::Blob(bytes) => format!("X'{}'", bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<String>()),
+        Value::Float(float) => format!("{:?}", float),
+        Value::Date(date) => format!("{:?}", date),
+        Value::Timestamp(timestamp) => format!("{:?}", timestamp),
+        Value::Decimal(decimal) => format!("{:?}", decimal),
+    }
+}
+
+pub fn markdown_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut table = format!("| {} |\n", headers.join(" | "));
+    table.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    table
+}
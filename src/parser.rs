@@ -1,4 +1,17 @@
+use std::cmp::Ordering;
+
 use crate::tokenizer::Token;
+use crate::temporal::{Date, Timestamp, TimeZone};
+use crate::decimal::{self, Decimal};
+use crate::pattern::LikePattern;
+use crate::schedule::Schedule;
+use crate::functions::{self, AggregateFunction, RankingFunction};
+use crate::coercion;
+use crate::evaluate;
+use crate::session::Session;
+
+// Engine-maintained column of tables created WITH ROW VERSION.
+const ROW_VERSION_COLUMN: &str = "_version";
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -7,14 +20,311 @@ pub enum Query {
     Insert(InsertQuery),
     Update(UpdateQuery),
     Delete(DeleteQuery),
+    Savepoint(SavepointQuery),
+    RollbackTo(SavepointQuery),
+    Release(SavepointQuery),
+    CreateSnapshot(SnapshotQuery),
+    AttachSnapshot(AttachSnapshotQuery),
+    Attach(AttachQuery),
+    CreateTable(CreateTableQuery),
+    CreateJob(CreateJobQuery),
+    DeclareCursor(DeclareCursorQuery),
+    Fetch(FetchQuery),
+    CloseCursor(CursorQuery),
+    Set(SetQuery),
+    Notify(NotifyQuery),
+    Listen(ChannelQuery),
+    Unlisten(ChannelQuery),
+}
+
+impl Query {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Query::Select(_) => "SELECT",
+            Query::Insert(_) => "INSERT",
+            Query::Update(_) => "UPDATE",
+            Query::Delete(_) => "DELETE",
+            Query::Savepoint(_) => "SAVEPOINT",
+            Query::RollbackTo(_) => "ROLLBACK TO",
+            Query::Release(_) => "RELEASE",
+            Query::CreateSnapshot(_) => "SNAPSHOT CREATE",
+            Query::AttachSnapshot(_) => "ATTACH SNAPSHOT",
+            Query::Attach(_) => "ATTACH",
+            Query::CreateTable(_) => "CREATE TABLE",
+            Query::CreateJob(_) => "CREATE JOB",
+            Query::DeclareCursor(_) => "DECLARE CURSOR",
+            Query::Fetch(_) => "FETCH",
+            Query::CloseCursor(_) => "CLOSE",
+            Query::Set(_) => "SET",
+            Query::Notify(_) => "NOTIFY",
+            Query::Listen(_) => "LISTEN",
+            Query::Unlisten(_) => "UNLISTEN",
+        }
+    }
+
+    pub fn is_unrestricted_write(&self) -> bool {
+        match self {
+            Query::Update(update) => update.where_clause.is_none(),
+            Query::Delete(delete) => delete.where_clause.is_none(),
+            _ => false,
+        }
+    }
+
+    // Sandboxed sessions may only read table data and change their own settings.
+    pub fn check_sandbox(&self) -> Result<(), String> {
+        let select = match self {
+            Query::Select(select) => select,
+            Query::DeclareCursor(declare) => &declare.query,
+            Query::Fetch(_) | Query::CloseCursor(_) | Query::Set(_) => return Ok(()),
+            _ => return Err(format!("{} is not allowed in sandbox mode", self.kind())),
+        };
+        if select.uses_table_function() {
+            return Err("PRAGMA and table functions are not allowed in sandbox mode".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn compares_with_null(&self) -> bool {
+        let where_clause = match self {
+            Query::Select(select) => &select.where_clause,
+            Query::Update(update) => &update.where_clause,
+            Query::Delete(delete) => &delete.where_clause,
+            Query::DeclareCursor(declare) => &declare.query.where_clause,
+            Query::Insert(InsertQuery { source: InsertSource::Select(select), .. }) => &select.where_clause,
+            _ => &None,
+        };
+        where_clause.as_ref().is_some_and(|condition| condition.compares_with_null())
+    }
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct SelectQuery {
-    selected_columns: Vec<Column>,
-    table_name: Table,
+    with: Option<WithClause>,
+    distinct: bool,
+    selected_columns: Vec<SelectItem>,
+    // None for a SELECT of expressions only, e.g. SELECT 1 + 1.
+    from: Option<TableSource>,
+    joins: Vec<Join>,
     where_clause: Option<Condition>,
+    group_by: Vec<Expression>,
+    having: Option<Condition>,
+    set_operations: Vec<SetOperation>,
+    order_by: Vec<OrderByItem>,
+    limit: Option<u64>,
+    offset: u64,
+}
+
+impl SelectQuery {
+    fn uses_table_function(&self) -> bool {
+        self.from
+            .iter()
+            .chain(self.joins.iter().map(|join| &join.source))
+            .any(|source| matches!(source, TableSource::Function(_)))
+            || self.subqueries().iter().any(|query| query.uses_table_function())
+    }
+
+    // Whether FROM or a JOIN of this query, not counting set operation members, reads the table.
+    fn reads_table(&self, name: &str) -> bool {
+        self.from
+            .iter()
+            .chain(self.joins.iter().map(|join| &join.source))
+            .any(|source| match source {
+                TableSource::Table(table) => table.database.is_none() && table.name.eq_ignore_ascii_case(name),
+                _ => false,
+            })
+    }
+
+    // The subqueries nested directly in this query's clauses.
+    fn subqueries(&self) -> Vec<&SelectQuery> {
+        let mut subqueries = Vec::new();
+        if let Some(with) = &self.with {
+            subqueries.extend(with.ctes.iter().map(|cte| &cte.query));
+        }
+        for item in &self.selected_columns {
+            if let SelectItem::Expression(expression, _) = item {
+                expression.collect_subqueries(&mut subqueries);
+            }
+        }
+        for join in &self.joins {
+            if let JoinConstraint::On(condition) = &join.constraint {
+                condition.collect_subqueries(&mut subqueries);
+            }
+        }
+        for condition in self.where_clause.iter().chain(&self.having) {
+            condition.collect_subqueries(&mut subqueries);
+        }
+        for key in &self.group_by {
+            key.collect_subqueries(&mut subqueries);
+        }
+        for item in &self.order_by {
+            item.expression.collect_subqueries(&mut subqueries);
+        }
+        for operation in &self.set_operations {
+            subqueries.push(&operation.query);
+        }
+        subqueries
+    }
+
+    // Without GROUP BY, any aggregate makes the whole result a single group.
+    fn validate_grouping(&self) -> Result<(), String> {
+        let aggregated = !self.group_by.is_empty()
+            || self.having.is_some()
+            || self.selected_columns.iter().any(|item| {
+                matches!(item, SelectItem::Expression(expression, _) if expression.contains_aggregate())
+            });
+        if !aggregated {
+            return Ok(());
+        }
+
+        let mut columns = Vec::new();
+        for item in &self.selected_columns {
+            match item {
+                SelectItem::Wildcard => {
+                    return Err("SELECT * cannot be combined with GROUP BY or aggregate functions".to_string());
+                }
+                SelectItem::Expression(expression, _) => {
+                    expression.collect_ungrouped_columns(&self.group_by, &mut columns)
+                }
+            }
+        }
+        if let Some(having) = &self.having {
+            having.collect_ungrouped_columns(&self.group_by, &mut columns);
+        }
+        // ORDER BY may also name a select-list alias, e.g. ORDER BY total. After a
+        // set operation it sorts the combined output instead of this query's groups.
+        let order_by = if self.set_operations.is_empty() { self.order_by.as_slice() } else { &[] };
+        for item in order_by {
+            let mut order_columns = Vec::new();
+            item.expression.collect_ungrouped_columns(&self.group_by, &mut order_columns);
+            columns.extend(order_columns.into_iter().filter(|column| !self.is_select_alias(column)));
+        }
+
+        match columns.first() {
+            Some(column) => Err(format!("Column '{}' must appear in GROUP BY or be used in an aggregate function", column.name)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.from.is_none()
+    }
+
+    // The alias of each output column, or columnN for the N-th one without an alias.
+    pub fn column_names(&self) -> Vec<String> {
+        self.selected_columns
+            .iter()
+            .enumerate()
+            .map(|(index, item)| match item {
+                SelectItem::Expression(_, Some(alias)) => alias.clone(),
+                _ => format!("column{}", index + 1),
+            })
+            .collect()
+    }
+
+    // Runs a SELECT without FROM. It reads no tables, so it returns a single
+    // row, or none when WHERE is not true or LIMIT and OFFSET skip the row.
+    pub fn evaluate_constant(&self, session: &Session) -> Result<Vec<Vec<Value>>, String> {
+        if self.from.is_some() {
+            return Err("Only a SELECT without FROM can be evaluated".to_string());
+        }
+        if self.with.is_some() || !self.set_operations.is_empty() || !self.group_by.is_empty() || self.having.is_some() {
+            return Err("WITH, set operations, GROUP BY and HAVING are not supported in a SELECT without FROM".to_string());
+        }
+        if let Some(condition) = &self.where_clause {
+            if condition.evaluate(session)? != Some(true) {
+                return Ok(Vec::new());
+            }
+        }
+
+        let mut row = Vec::new();
+        for item in &self.selected_columns {
+            if let SelectItem::Expression(expression, _) = item {
+                row.push(expression.evaluate(session)?);
+            }
+        }
+        if self.limit == Some(0) || self.offset > 0 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![row])
+    }
+
+    fn is_select_alias(&self, column: &Column) -> bool {
+        column.table.is_none()
+            && self.selected_columns.iter().any(|item| {
+                matches!(item, SelectItem::Expression(_, Some(alias)) if alias.eq_ignore_ascii_case(&column.name))
+            })
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum SelectItem {
+    Wildcard,
+    Expression(Expression, Option<String>),
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct WithClause {
+    recursive: bool,
+    ctes: Vec<CommonTableExpression>,
+}
+
+// A recursive CTE is 'anchor UNION [ALL] recursive member', where only the
+// recursive member reads the CTE itself; it is re-run until it adds no rows.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct CommonTableExpression {
+    name: String,
+    columns: Vec<String>,
+    query: SelectQuery,
+}
+
+// Members are combined left to right, e.g. 'a UNION b EXCEPT c' is '(a UNION b) EXCEPT c'.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct SetOperation {
+    operator: SetOperator,
+    // ALL keeps duplicates (bag semantics).
+    all: bool,
+    query: SelectQuery,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl SetOperator {
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Keyword(keyword) => match keyword.as_str() {
+                "UNION" => Some(SetOperator::Union),
+                "INTERSECT" => Some(SetOperator::Intersect),
+                "EXCEPT" => Some(SetOperator::Except),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct OrderByItem {
+    expression: Expression,
+    descending: bool,
+    // None sorts NULLs last.
+    nulls: Option<NullsOrder>,
+}
+
+#[derive(Debug)]
+pub enum NullsOrder {
+    First,
+    Last,
 }
 
 #[derive(Debug)]
@@ -22,7 +332,37 @@ pub struct SelectQuery {
 pub struct InsertQuery{
     table_name: Table,
     columns: Vec<Column>,
-    values: Vec<Value>
+    source: InsertSource,
+    on_conflict: Option<OnConflict>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum InsertSource {
+    // One entry per VALUES tuple; all of them are inserted or none is.
+    Values(Vec<Vec<Value>>),
+    // Rows are streamed from the query into the table.
+    Select(Box<SelectQuery>),
+}
+
+// What to do with a row that violates the primary key or a unique constraint.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct OnConflict {
+    // Columns of the violated constraint; empty matches any constraint.
+    target: Vec<String>,
+    action: ConflictAction,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum ConflictAction {
+    Nothing,
+    // The proposed row is available as the table `excluded`.
+    Update {
+        changes: Vec<UpdateSet>,
+        where_clause: Option<Condition>,
+    },
 }
 
 #[derive(Debug)]
@@ -42,270 +382,2704 @@ pub struct UpdateQuery{
 
 #[derive(Debug)]
 #[allow(dead_code)]
-pub struct UpdateSet{
-    column: Column,
-    value: Value,
+pub struct CreateTableQuery{
+    table_name: Table,
+    columns: Vec<ColumnDefinition>,
+    // Column constraints are stored here too, as if they had been declared for the table.
+    constraints: Vec<TableConstraint>,
+    row_version: bool,
+    // Updates and deletes copy the previous row into <table>_history.
+    system_versioned: bool,
+}
+
+impl CreateTableQuery {
+    fn validate_constraints(&self) -> Result<(), String> {
+        let primary_keys = self
+            .constraints
+            .iter()
+            .filter(|constraint| matches!(constraint.kind, ConstraintKind::PrimaryKey(_)))
+            .count();
+        if primary_keys > 1 {
+            return Err(format!("Table '{}' has more than one PRIMARY KEY", self.table_name.name));
+        }
+
+        for constraint in &self.constraints {
+            for name in constraint.kind.columns() {
+                if !self.columns.iter().any(|definition| definition.column.name.eq_ignore_ascii_case(name)) {
+                    return Err(format!("Constraint {} refers to unknown column '{}'", constraint.name, name));
+                }
+            }
+            if let ConstraintKind::ForeignKey(foreign_key) = &constraint.kind {
+                if foreign_key.on_delete == ReferentialAction::SetNull {
+                    let key_column = self.columns.iter().find(|definition| {
+                        definition.not_null
+                            && foreign_key.columns.iter().any(|name| name.eq_ignore_ascii_case(&definition.column.name))
+                    });
+                    if let Some(definition) = key_column {
+                        return Err(format!(
+                            "Constraint {} uses ON DELETE SET NULL but column '{}' is NOT NULL",
+                            constraint.name, definition.column.name
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Unnamed constraints are named after the table and their columns, as in
+// orders_pkey, orders_email_key and orders_check, with a number added on a clash.
+fn name_constraints(table: &str, declared: Vec<(Option<String>, ConstraintKind)>) -> Result<Vec<TableConstraint>, String> {
+    let explicit: Vec<String> = declared.iter().filter_map(|(name, _)| name.clone()).collect();
+    for (index, name) in explicit.iter().enumerate() {
+        if explicit[..index].iter().any(|earlier| earlier.eq_ignore_ascii_case(name)) {
+            return Err(format!("Constraint name '{}' is used more than once", name));
+        }
+    }
+
+    let mut constraints: Vec<TableConstraint> = Vec::new();
+    for (name, kind) in declared {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let base = match &kind {
+                    ConstraintKind::PrimaryKey(_) => format!("{}_pkey", table),
+                    ConstraintKind::Unique(columns) => format!("{}_{}_key", table, columns.join("_")),
+                    ConstraintKind::Check(_) => format!("{}_check", table),
+                    ConstraintKind::ForeignKey(foreign_key) => format!("{}_{}_fkey", table, foreign_key.columns.join("_")),
+                };
+                let taken = |name: &str| {
+                    explicit.iter().any(|existing| existing.eq_ignore_ascii_case(name))
+                        || constraints.iter().any(|constraint| constraint.name.eq_ignore_ascii_case(name))
+                };
+                let mut name = base.clone();
+                let mut suffix = 1;
+                while taken(&name) {
+                    name = format!("{}{}", base, suffix);
+                    suffix += 1;
+                }
+                name
+            }
+        };
+        constraints.push(TableConstraint { name, kind });
+    }
+    Ok(constraints)
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
-pub struct Column {
+pub struct TableConstraint {
+    // Violations are reported with this name, given with CONSTRAINT name or generated.
     name: String,
+    kind: ConstraintKind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 #[allow(dead_code)]
-pub struct Table {
+pub enum ConstraintKind {
+    // Backed by a unique index; the key columns cannot be NULL.
+    PrimaryKey(Vec<String>),
+    // Backed by a unique index. NULLs never conflict, so any number of rows may hold them.
+    Unique(Vec<String>),
+    // Rejects rows for which the condition is false; NULL (unknown) passes.
+    Check(Condition),
+    ForeignKey(ForeignKey),
+}
+
+// A row whose key columns are all non-NULL must match a row of the parent table.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct ForeignKey {
+    columns: Vec<String>,
+    parent: Table,
+    // None refers to the parent's primary key.
+    parent_columns: Option<Vec<String>>,
+    on_delete: ReferentialAction,
+}
+
+// What happens to referencing rows when their parent row is deleted.
+#[derive(Debug, PartialEq)]
+pub enum ReferentialAction {
+    // The delete fails while referencing rows exist. The default.
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+impl ConstraintKind {
+    fn columns(&self) -> Vec<&str> {
+        match self {
+            ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) => {
+                columns.iter().map(String::as_str).collect()
+            }
+            ConstraintKind::Check(condition) => {
+                let mut columns = Vec::new();
+                condition.collect_bare_columns(&mut columns);
+                columns.into_iter().map(|column| column.name.as_str()).collect()
+            }
+            ConstraintKind::ForeignKey(foreign_key) => foreign_key.columns.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct CreateJobQuery{
     name: String,
+    schedule: Schedule,
+    statement: Box<Query>,
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
-pub struct Condition {
-    left: ConditionEnum,
-    operator: Operator,
-    right: ConditionEnum,
+pub struct ColumnDefinition{
+    column: Column,
+    data_type: DataType,
+    // Values are compressed on write and decompressed on read.
+    compressed: bool,
+    // Set by NOT NULL and for primary key columns.
+    not_null: bool,
+    // Filled in by INSERTs that omit the column; NULL when absent.
+    default: Option<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum DataType {
+    Integer,
+    Float,
+    Text,
+    Boolean,
+    Date,
+    Timestamp,
+    TimestampTz,
+    Decimal { precision: u32, scale: u32 },
+    Blob,
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
-pub enum ConditionEnum {
-    Field(Column),
-    Value(Value),
+pub struct SavepointQuery{
+    name: String,
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
-pub enum Operator {
-    Equal,
-    NotEqual,
-    LessThan,
-    LessOrEqual,
-    GreaterThan,
-    GreaterOrEqual,
+pub struct SnapshotQuery{
+    name: String,
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
-pub enum Value {
-    Integer(i64),
-    Float(f64),
-    Text(String),
+pub struct AttachSnapshotQuery{
+    snapshot: SnapshotQuery,
+    alias: Option<String>,
 }
 
-pub struct Parser<'a> {
-    tokens: &'a Vec<Token>,
-    position: usize,
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct AttachQuery{
+    path: String,
+    alias: String,
 }
 
-impl <'a>Parser<'a>{
-    pub fn new(token_stream: &'a Vec<Token>) -> Self {
-        Parser {
-            tokens: token_stream,
-            position: 0,
-        }
-    }
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct CursorQuery{
+    name: String,
+}
 
-    pub fn parse(&mut self) -> Result<Query, String> {
-        let start = self.advance();
-        match start {
-            Token::Keyword(ref keyword) => match keyword.as_str() {
-                "SELECT" => Ok(Query::Select(self.handle_select()?)),
-                "INSERT" => Ok(Query::Insert(self.handle_insert()?)),
-                "UPDATE" => Ok(Query::Update(self.handle_update()?)),
-                "DELETE" => Ok(Query::Delete(self.handle_delete()?)),
-                _ => Err("Invalid query type".to_string()),
-            },
-            _ => Err("Expected keyword token at the beginning!".to_string()),
-        }
-    }
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct DeclareCursorQuery{
+    cursor: CursorQuery,
+    query: SelectQuery,
+}
 
-    fn handle_select(&mut self) -> Result<SelectQuery, String> {
-        let columns = self.parse_column_list()?;
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct FetchQuery{
+    cursor: CursorQuery,
+    count: u64,
+}
 
-        self.consume_token(Token::Keyword("FROM".to_string()))?;
-        let table = self.parse_table()?;
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct ChannelQuery{
+    name: String,
+}
 
-        let where_clause = if self.check_keyword("WHERE") {
-            self.advance(); 
-            Some(self.parse_condition()?)
-        } else {
-            None
-        };
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct NotifyQuery{
+    channel: ChannelQuery,
+    payload: Option<String>,
+}
 
-        Ok(SelectQuery {
-            selected_columns: columns,
-            table_name: table,
-            where_clause,
-        })
+#[derive(Debug)]
+pub struct SetQuery{
+    name: String,
+    value: Value,
+}
+
+impl SetQuery {
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    fn handle_insert(&mut self) -> Result<InsertQuery, String>{
-        self.consume_token(Token::Keyword("INTO".to_string()))?;
-        let table = self.parse_table()?;
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
 
-        self.consume_token(Token::Delimiter('('))?;
-        let columns = self.parse_column_list()?;
-        self.consume_token(Token::Delimiter(')'))?;
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct UpdateSet{
+    column: Column,
+    value: Expression,
+}
 
-        self.consume_token(Token::Keyword("VALUES".to_string()))?;
-        self.consume_token(Token::Delimiter('('))?;
-        let values = self.parse_value_list()?;
-        self.consume_token(Token::Delimiter(')'))?;
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Column {
+    name: String,
+    table: Option<String>,
+}
 
-        Ok(InsertQuery {
-            table_name: table,
-            columns: columns, 
-            values: values
-        })
+impl Column {
+    // An unqualified name matches the same column qualified by any table.
+    fn matches(&self, other: &Column) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name)
+            && match (&self.table, &other.table) {
+                (Some(table), Some(other_table)) => table.eq_ignore_ascii_case(other_table),
+                _ => true,
+            }
     }
+}
+
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct Table {
+    name: String,
+    database: Option<String>,
+    alias: Option<String>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum TableSource {
+    Table(Table),
+    SystemTime(SystemTimeTable),
+    Function(TableFunctionCall),
+}
+
+// FOR SYSTEM_TIME AS OF: the rows of a system-versioned table valid at a point in time.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct SystemTimeTable {
+    table: Table,
+    as_of: Value,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Join {
+    kind: JoinKind,
+    source: TableSource,
+    constraint: JoinConstraint,
+}
+
+// USING and NATURAL joins output each shared column once, coalesced from both sides.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum JoinConstraint {
+    On(Condition),
+    Using(Vec<String>),
+    Natural,
+    // Cross joins pair every row with every row.
+    None,
+}
+
+#[derive(Debug)]
+pub enum JoinKind {
+    Inner,
+    // Outer joins pad the non-matching side with NULLs.
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct TableFunctionCall {
+    name: String,
+    arguments: Vec<Value>,
+    alias: Option<String>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Condition {
+    Comparison(Comparison),
+    IsNull(IsNullCondition),
+    Expression(Expression),
+    In(InCondition),
+    Like(LikeCondition),
+    Exists(Box<SelectQuery>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    fn compares_with_null(&self) -> bool {
+        match self {
+            Condition::Comparison(comparison) => {
+                matches!(comparison.operator, Operator::Equal | Operator::NotEqual)
+                    && (matches!(comparison.left, Expression::Value(Value::Null))
+                        || matches!(comparison.right, Expression::Value(Value::Null)))
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.compares_with_null() || right.compares_with_null()
+            }
+            Condition::Not(condition) => condition.compares_with_null(),
+            _ => false,
+        }
+    }
+
+    fn contains_aggregate(&self) -> bool {
+        match self {
+            Condition::Comparison(comparison) => {
+                comparison.left.contains_aggregate() || comparison.right.contains_aggregate()
+            }
+            Condition::IsNull(is_null) => is_null.expression.contains_aggregate(),
+            Condition::Expression(expression) => expression.contains_aggregate(),
+            Condition::In(in_condition) => in_condition.expression.contains_aggregate(),
+            Condition::Like(like) => like.expression.contains_aggregate(),
+            // A subquery aggregates its own rows, not the outer query's.
+            Condition::Exists(_) => false,
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.contains_aggregate() || right.contains_aggregate()
+            }
+            Condition::Not(condition) => condition.contains_aggregate(),
+        }
+    }
+
+    fn collect_bare_columns<'e>(&'e self, columns: &mut Vec<&'e Column>) {
+        self.collect_ungrouped_columns(&[], columns);
+    }
+
+    fn collect_ungrouped_columns<'e>(&'e self, keys: &[Expression], columns: &mut Vec<&'e Column>) {
+        match self {
+            Condition::Comparison(comparison) => {
+                comparison.left.collect_ungrouped_columns(keys, columns);
+                comparison.right.collect_ungrouped_columns(keys, columns);
+            }
+            Condition::IsNull(is_null) => is_null.expression.collect_ungrouped_columns(keys, columns),
+            Condition::Expression(expression) => expression.collect_ungrouped_columns(keys, columns),
+            Condition::In(in_condition) => in_condition.expression.collect_ungrouped_columns(keys, columns),
+            Condition::Like(like) => like.expression.collect_ungrouped_columns(keys, columns),
+            Condition::Exists(_) => {}
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.collect_ungrouped_columns(keys, columns);
+                right.collect_ungrouped_columns(keys, columns);
+            }
+            Condition::Not(condition) => condition.collect_ungrouped_columns(keys, columns),
+        }
+    }
+
+    // Three-valued: None is unknown, e.g. for a comparison with NULL.
+    fn evaluate(&self, session: &Session) -> Result<Option<bool>, String> {
+        match self {
+            Condition::Comparison(comparison) => {
+                let ordering = evaluate::compare(comparison.left.evaluate(session)?, comparison.right.evaluate(session)?)?;
+                Ok(ordering.map(|ordering| match comparison.operator {
+                    Operator::Equal => ordering == Ordering::Equal,
+                    Operator::NotEqual => ordering != Ordering::Equal,
+                    Operator::LessThan => ordering == Ordering::Less,
+                    Operator::LessOrEqual => ordering != Ordering::Greater,
+                    Operator::GreaterThan => ordering == Ordering::Greater,
+                    Operator::GreaterOrEqual => ordering != Ordering::Less,
+                }))
+            }
+            Condition::IsNull(is_null) => Ok(Some((is_null.expression.evaluate(session)? == Value::Null) != is_null.negated)),
+            Condition::Expression(expression) => match expression.evaluate(session)? {
+                Value::Boolean(boolean) => Ok(Some(boolean)),
+                Value::Null => Ok(None),
+                other => Err(format!("Expected a BOOLEAN condition, got {:?}", other)),
+            },
+            Condition::In(in_condition) => {
+                let values = match &in_condition.list {
+                    InList::Values(values) => values,
+                    InList::Subquery(_) => return Err("IN (SELECT ...) is not supported in a SELECT without FROM".to_string()),
+                };
+                let value = in_condition.expression.evaluate(session)?;
+                let mut unknown = false;
+                for candidate in values {
+                    match evaluate::compare(value.clone(), candidate.clone())? {
+                        Some(Ordering::Equal) => return Ok(Some(!in_condition.negated)),
+                        Some(_) => {}
+                        None => unknown = true,
+                    }
+                }
+                Ok(if unknown { None } else { Some(in_condition.negated) })
+            }
+            Condition::Like(like) => match like.expression.evaluate(session)? {
+                Value::Null => Ok(None),
+                Value::Text(text) => Ok(Some(like.pattern.matches(&text) != like.negated)),
+                other => Err(format!("LIKE expects TEXT, got {:?}", other)),
+            },
+            Condition::Exists(_) => Err("EXISTS is not supported in a SELECT without FROM".to_string()),
+            Condition::And(left, right) => match left.evaluate(session)? {
+                Some(false) => Ok(Some(false)),
+                left => Ok(match right.evaluate(session)? {
+                    Some(false) => Some(false),
+                    right => left.and(right),
+                }),
+            },
+            Condition::Or(left, right) => match left.evaluate(session)? {
+                Some(true) => Ok(Some(true)),
+                left => Ok(match right.evaluate(session)? {
+                    Some(true) => Some(true),
+                    right => left.and(right),
+                }),
+            },
+            Condition::Not(condition) => Ok(condition.evaluate(session)?.map(|value| !value)),
+        }
+    }
+
+    fn collect_subqueries<'e>(&'e self, subqueries: &mut Vec<&'e SelectQuery>) {
+        match self {
+            Condition::Comparison(comparison) => {
+                comparison.left.collect_subqueries(subqueries);
+                comparison.right.collect_subqueries(subqueries);
+            }
+            Condition::IsNull(is_null) => is_null.expression.collect_subqueries(subqueries),
+            Condition::Expression(expression) => expression.collect_subqueries(subqueries),
+            Condition::In(in_condition) => {
+                in_condition.expression.collect_subqueries(subqueries);
+                if let InList::Subquery(query) = &in_condition.list {
+                    subqueries.push(query);
+                }
+            }
+            Condition::Like(like) => like.expression.collect_subqueries(subqueries),
+            Condition::Exists(query) => subqueries.push(query),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.collect_subqueries(subqueries);
+                right.collect_subqueries(subqueries);
+            }
+            Condition::Not(condition) => condition.collect_subqueries(subqueries),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Comparison {
+    left: Expression,
+    operator: Operator,
+    right: Expression,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct IsNullCondition {
+    expression: Expression,
+    negated: bool,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct InCondition {
+    expression: Expression,
+    list: InList,
+    negated: bool,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum InList {
+    Values(Vec<Value>),
+    // Selects exactly one column.
+    Subquery(Box<SelectQuery>),
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct LikeCondition {
+    expression: Expression,
+    pattern: LikePattern,
+    negated: bool,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Expression {
+    Field(Column),
+    Value(Value),
+    Binary(BinaryExpression),
+    AtTimeZone(AtTimeZoneExpression),
+    Function(FunctionCall),
+    Aggregate(AggregateCall),
+    Window(WindowCall),
+    Case(CaseExpression),
+    Cast(CastExpression),
+    Negate(Box<Expression>),
+    // A condition used as a boolean value, e.g. the first argument of IIF.
+    Predicate(Box<Condition>),
+    // Selects one column and must return at most one row.
+    Subquery(Box<SelectQuery>),
+}
+
+impl Expression {
+    fn contains_aggregate(&self) -> bool {
+        match self {
+            Expression::Aggregate(_) => true,
+            Expression::Binary(binary) => binary.left.contains_aggregate() || binary.right.contains_aggregate(),
+            Expression::AtTimeZone(at_time_zone) => at_time_zone.expression.contains_aggregate(),
+            Expression::Function(function) => function.arguments.iter().any(Expression::contains_aggregate),
+            // A windowed aggregate does not collapse rows, but its window may use the query's aggregates.
+            Expression::Window(window) => window.over.expressions().any(Expression::contains_aggregate),
+            Expression::Case(case) => {
+                case.operand.as_deref().is_some_and(Expression::contains_aggregate)
+                    || case.branches.iter().any(|branch| branch.condition.contains_aggregate() || branch.result.contains_aggregate())
+                    || case.else_result.contains_aggregate()
+            }
+            Expression::Cast(cast) => cast.expression.contains_aggregate(),
+            Expression::Negate(operand) => operand.contains_aggregate(),
+            Expression::Predicate(condition) => condition.contains_aggregate(),
+            Expression::Field(_) | Expression::Value(_) | Expression::Subquery(_) => false,
+        }
+    }
+
+    fn collect_subqueries<'e>(&'e self, subqueries: &mut Vec<&'e SelectQuery>) {
+        match self {
+            Expression::Binary(binary) => {
+                binary.left.collect_subqueries(subqueries);
+                binary.right.collect_subqueries(subqueries);
+            }
+            Expression::AtTimeZone(at_time_zone) => at_time_zone.expression.collect_subqueries(subqueries),
+            Expression::Function(function) => {
+                for argument in &function.arguments {
+                    argument.collect_subqueries(subqueries);
+                }
+            }
+            Expression::Aggregate(aggregate) => {
+                if let AggregateArgument::Expression(argument) = &aggregate.argument {
+                    argument.collect_subqueries(subqueries);
+                }
+            }
+            Expression::Window(window) => {
+                for expression in window.expressions() {
+                    expression.collect_subqueries(subqueries);
+                }
+            }
+            Expression::Case(case) => {
+                if let Some(operand) = &case.operand {
+                    operand.collect_subqueries(subqueries);
+                }
+                for branch in &case.branches {
+                    branch.condition.collect_subqueries(subqueries);
+                    branch.result.collect_subqueries(subqueries);
+                }
+                case.else_result.collect_subqueries(subqueries);
+            }
+            Expression::Cast(cast) => cast.expression.collect_subqueries(subqueries),
+            Expression::Negate(operand) => operand.collect_subqueries(subqueries),
+            Expression::Predicate(condition) => condition.collect_subqueries(subqueries),
+            Expression::Subquery(query) => subqueries.push(query),
+            Expression::Field(_) | Expression::Value(_) => {}
+        }
+    }
+
+    // Value of an expression that reads no columns, as in a SELECT without FROM.
+    fn evaluate(&self, session: &Session) -> Result<Value, String> {
+        match self {
+            Expression::Field(column) => Err(format!("Column '{}' needs a FROM clause", column.name)),
+            Expression::Value(value) => Ok(value.clone()),
+            Expression::Binary(binary) => {
+                evaluate::binary(binary.operator, binary.left.evaluate(session)?, binary.right.evaluate(session)?)
+            }
+            Expression::Function(function) => function.evaluate(session),
+            Expression::Case(case) => case.evaluate(session),
+            Expression::Cast(cast) => coercion::cast(cast.expression.evaluate(session)?, &cast.data_type),
+            Expression::Negate(expression) => negate_value(expression.evaluate(session)?),
+            Expression::Predicate(condition) => Ok(condition.evaluate(session)?.map_or(Value::Null, Value::Boolean)),
+            Expression::Aggregate(_) | Expression::Window(_) => {
+                Err("Aggregate and window functions need a FROM clause".to_string())
+            }
+            Expression::AtTimeZone(_) => Err("AT TIME ZONE is not supported in a SELECT without FROM".to_string()),
+            Expression::Subquery(_) => Err("Subqueries are not supported in a SELECT without FROM".to_string()),
+        }
+    }
+
+    // The type of a literal or a CAST; other expressions are only typed at execution.
+    fn static_type(&self) -> Option<DataType> {
+        match self {
+            Expression::Value(value) => coercion::value_type(value),
+            Expression::Cast(cast) => Some(cast.data_type.clone()),
+            _ => None,
+        }
+    }
+
+    // Collects the columns referenced outside of aggregate calls.
+    fn collect_bare_columns<'e>(&'e self, columns: &mut Vec<&'e Column>) {
+        self.collect_ungrouped_columns(&[], columns);
+    }
+
+    // Like collect_bare_columns, but skips subexpressions that are GROUP BY keys.
+    fn collect_ungrouped_columns<'e>(&'e self, keys: &[Expression], columns: &mut Vec<&'e Column>) {
+        if keys.iter().any(|key| key.same_as(self)) {
+            return;
+        }
+        match self {
+            Expression::Field(column) => columns.push(column),
+            Expression::Binary(binary) => {
+                binary.left.collect_ungrouped_columns(keys, columns);
+                binary.right.collect_ungrouped_columns(keys, columns);
+            }
+            Expression::AtTimeZone(at_time_zone) => at_time_zone.expression.collect_ungrouped_columns(keys, columns),
+            Expression::Function(function) => {
+                for argument in &function.arguments {
+                    argument.collect_ungrouped_columns(keys, columns);
+                }
+            }
+            Expression::Window(window) => {
+                for expression in window.expressions() {
+                    expression.collect_ungrouped_columns(keys, columns);
+                }
+            }
+            Expression::Case(case) => {
+                if let Some(operand) = &case.operand {
+                    operand.collect_ungrouped_columns(keys, columns);
+                }
+                for branch in &case.branches {
+                    branch.condition.collect_ungrouped_columns(keys, columns);
+                    branch.result.collect_ungrouped_columns(keys, columns);
+                }
+                case.else_result.collect_ungrouped_columns(keys, columns);
+            }
+            Expression::Cast(cast) => cast.expression.collect_ungrouped_columns(keys, columns),
+            Expression::Negate(operand) => operand.collect_ungrouped_columns(keys, columns),
+            Expression::Predicate(condition) => condition.collect_ungrouped_columns(keys, columns),
+            Expression::Value(_) | Expression::Aggregate(_) | Expression::Subquery(_) => {}
+        }
+    }
+
+    // Structural equality, used to match select-list expressions against GROUP BY
+    // keys. Only the usual kinds of key are compared; any other pair is different.
+    fn same_as(&self, other: &Expression) -> bool {
+        match (self, other) {
+            (Expression::Field(left), Expression::Field(right)) => left.matches(right),
+            (Expression::Value(left), Expression::Value(right)) => left == right,
+            (Expression::Binary(left), Expression::Binary(right)) => {
+                left.operator == right.operator && left.left.same_as(&right.left) && left.right.same_as(&right.right)
+            }
+            (Expression::AtTimeZone(left), Expression::AtTimeZone(right)) => {
+                left.zone == right.zone && left.expression.same_as(&right.expression)
+            }
+            (Expression::Function(left), Expression::Function(right)) => {
+                left.name.eq_ignore_ascii_case(&right.name)
+                    && left.arguments.len() == right.arguments.len()
+                    && left.arguments.iter().zip(&right.arguments).all(|(left, right)| left.same_as(right))
+            }
+            (Expression::Cast(left), Expression::Cast(right)) => {
+                left.data_type == right.data_type && left.expression.same_as(&right.expression)
+            }
+            (Expression::Negate(left), Expression::Negate(right)) => left.same_as(right),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct CastExpression {
+    expression: Box<Expression>,
+    data_type: DataType,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct FunctionCall {
+    name: String,
+    arguments: Vec<Expression>,
+}
+
+impl FunctionCall {
+    // COALESCE, IFNULL and IIF only evaluate the argument they return.
+    fn evaluate(&self, session: &Session) -> Result<Value, String> {
+        match self.name.as_str() {
+            "COALESCE" | "IFNULL" => {
+                for argument in &self.arguments {
+                    let value = argument.evaluate(session)?;
+                    if value != Value::Null {
+                        return Ok(value);
+                    }
+                }
+                Ok(Value::Null)
+            }
+            "IIF" => match self.arguments[0].evaluate(session)? {
+                Value::Boolean(true) => self.arguments[1].evaluate(session),
+                Value::Boolean(false) | Value::Null => self.arguments[2].evaluate(session),
+                other => Err(format!("IIF expects a BOOLEAN condition, got {:?}", other)),
+            },
+            name => {
+                let arguments = self.arguments.iter().map(|argument| argument.evaluate(session)).collect::<Result<_, _>>()?;
+                evaluate::call(name, arguments, session)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct AggregateCall {
+    function: AggregateFunction,
+    distinct: bool,
+    argument: AggregateArgument,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum AggregateArgument {
+    // COUNT(*) counts rows; COUNT(expression) skips NULLs.
+    Star,
+    Expression(Box<Expression>),
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct WindowCall {
+    function: WindowFunction,
+    over: WindowSpecification,
+}
+
+impl WindowCall {
+    // The aggregate's argument, evaluated per row, followed by the window's own expressions.
+    fn expressions(&self) -> impl Iterator<Item = &Expression> {
+        let argument = match &self.function {
+            WindowFunction::Aggregate(AggregateCall { argument: AggregateArgument::Expression(argument), .. }) => {
+                Some(argument.as_ref())
+            }
+            _ => None,
+        };
+        argument.into_iter().chain(self.over.expressions())
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum WindowFunction {
+    Ranking(RankingFunction),
+    // SUM(x) OVER (ORDER BY ...) is a running total over the rows up to the current one.
+    Aggregate(AggregateCall),
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct WindowSpecification {
+    partition_by: Vec<Expression>,
+    order_by: Vec<OrderByItem>,
+}
+
+impl WindowSpecification {
+    fn expressions(&self) -> impl Iterator<Item = &Expression> {
+        self.partition_by.iter().chain(self.order_by.iter().map(|item| &item.expression))
+    }
+}
+
+// Branches are tried in order and only the result of the first match is evaluated.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct CaseExpression {
+    // With an operand (CASE x WHEN 1 ...), each branch condition is a value compared to it with =.
+    operand: Option<Box<Expression>>,
+    branches: Vec<CaseBranch>,
+    // NULL when there is no ELSE.
+    else_result: Box<Expression>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct CaseBranch {
+    condition: Condition,
+    result: Expression,
+}
+
+impl CaseExpression {
+    fn evaluate(&self, session: &Session) -> Result<Value, String> {
+        let operand = self.operand.as_ref().map(|operand| operand.evaluate(session)).transpose()?;
+        for branch in &self.branches {
+            let matched = match (&operand, &branch.condition) {
+                (Some(operand), Condition::Expression(value)) => {
+                    evaluate::compare(operand.clone(), value.evaluate(session)?)? == Some(Ordering::Equal)
+                }
+                _ => branch.condition.evaluate(session)? == Some(true),
+            };
+            if matched {
+                return branch.result.evaluate(session);
+            }
+        }
+        self.else_result.evaluate(session)
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct AtTimeZoneExpression {
+    expression: Box<Expression>,
+    zone: TimeZone,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct BinaryExpression {
+    left: Box<Expression>,
+    operator: BinaryOperator,
+    right: Box<Expression>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Concat,
+    BitAnd,
+    BitOr,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl BinaryOperator {
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Operator(op) => match op.as_str() {
+                "+" => Some(BinaryOperator::Add),
+                "-" => Some(BinaryOperator::Subtract),
+                "*" => Some(BinaryOperator::Multiply),
+                "/" => Some(BinaryOperator::Divide),
+                "%" => Some(BinaryOperator::Modulo),
+                "||" => Some(BinaryOperator::Concat),
+                "&" => Some(BinaryOperator::BitAnd),
+                "|" => Some(BinaryOperator::BitOr),
+                "<<" => Some(BinaryOperator::ShiftLeft),
+                ">>" => Some(BinaryOperator::ShiftRight),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Concat => "||",
+            BinaryOperator::BitAnd => "&",
+            BinaryOperator::BitOr => "|",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Concat
+            | BinaryOperator::BitAnd
+            | BinaryOperator::BitOr
+            | BinaryOperator::ShiftLeft
+            | BinaryOperator::ShiftRight => 1,
+            BinaryOperator::Add | BinaryOperator::Subtract => 2,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 3,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessOrEqual,
+    GreaterThan,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    Boolean(bool),
+    Date(Date),
+    Timestamp(Timestamp),
+    TimestampTz(Timestamp),
+    Decimal(Decimal),
+    Blob(Vec<u8>),
+}
+
+pub struct Parser<'a> {
+    tokens: &'a Vec<Token>,
+    position: usize,
+    // Window functions may only appear in a select list or ORDER BY.
+    windows_allowed: bool,
+}
+
+impl <'a>Parser<'a>{
+    pub fn new(token_stream: &'a Vec<Token>) -> Self {
+        Parser {
+            tokens: token_stream,
+            position: 0,
+            windows_allowed: false,
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Query, String> {
+        let query = self.parse_statement()?;
+        match self.tokens.get(self.position) {
+            None | Some(Token::Delimiter(';')) => Ok(query),
+            Some(token) => Err(format!("Unexpected {:?} after the end of the statement", token)),
+        }
+    }
+
+    fn parse_statement(&mut self) -> Result<Query, String> {
+        let start = self.advance();
+        match start {
+            Token::Keyword(ref keyword) => match keyword.as_str() {
+                "SELECT" => Ok(Query::Select(self.handle_select()?)),
+                "WITH" => Ok(Query::Select(self.handle_with()?)),
+                "INSERT" => Ok(Query::Insert(self.handle_insert()?)),
+                "UPDATE" => Ok(Query::Update(self.handle_update()?)),
+                "DELETE" => Ok(Query::Delete(self.handle_delete()?)),
+                "CREATE" => self.handle_create(),
+                "SET" => Ok(Query::Set(self.handle_set_statement()?)),
+                _ => Err("Invalid query type".to_string()),
+            },
+            // Statements introduced by non-reserved words, which stay usable as column names.
+            Token::Identifier(ref word) => match word.to_uppercase().as_str() {
+                "DECLARE" => Ok(Query::DeclareCursor(self.handle_declare_cursor()?)),
+                "FETCH" => Ok(Query::Fetch(self.handle_fetch()?)),
+                "CLOSE" => Ok(Query::CloseCursor(self.parse_cursor_name()?)),
+                "SAVEPOINT" => Ok(Query::Savepoint(self.parse_savepoint_name()?)),
+                "ROLLBACK" => Ok(Query::RollbackTo(self.handle_rollback_to()?)),
+                "RELEASE" => Ok(Query::Release(self.handle_release()?)),
+                "SNAPSHOT" => Ok(Query::CreateSnapshot(self.handle_create_snapshot()?)),
+                "ATTACH" => self.handle_attach(),
+                "PRAGMA" => Ok(Query::Select(self.handle_pragma()?)),
+                "NOTIFY" => Ok(Query::Notify(self.handle_notify()?)),
+                "LISTEN" => Ok(Query::Listen(self.parse_channel_name()?)),
+                "UNLISTEN" => Ok(Query::Unlisten(self.parse_channel_name()?)),
+                _ => Err("Invalid query type".to_string()),
+            },
+            _ => Err("Expected keyword token at the beginning!".to_string()),
+        }
+    }
+
+    fn handle_select(&mut self) -> Result<SelectQuery, String> {
+        let mut query = self.parse_select_core()?;
+
+        while let Some(operator) = SetOperator::from_token(self.peek()) {
+            self.advance();
+            let all = self.check_keyword("ALL");
+            if all {
+                self.advance();
+            }
+            self.consume_token(Token::Keyword("SELECT".to_string()))?;
+            let member = self.parse_select_core()?;
+            member.validate_grouping()?;
+
+            let has_wildcard = |query: &SelectQuery| query.selected_columns.iter().any(|item| matches!(item, SelectItem::Wildcard));
+            if !has_wildcard(&query) && !has_wildcard(&member) && query.selected_columns.len() != member.selected_columns.len() {
+                return Err("Each member of a set operation must select the same number of columns".to_string());
+            }
+            query.set_operations.push(SetOperation {
+                operator,
+                all,
+                query: member,
+            });
+        }
+
+        query.order_by = self.with_windows(true, Self::parse_order_by)?;
+        (query.limit, query.offset) = self.parse_limit()?;
+        query.validate_grouping()?;
+        Ok(query)
+    }
+
+    fn handle_with(&mut self) -> Result<SelectQuery, String> {
+        let recursive = matches!(self.peek(), Token::Identifier(word) if word.eq_ignore_ascii_case("RECURSIVE"));
+        if recursive {
+            self.advance();
+        }
+
+        let mut ctes: Vec<CommonTableExpression> = Vec::new();
+        loop {
+            let cte = self.parse_common_table_expression(recursive)?;
+            if ctes.iter().any(|existing| existing.name.eq_ignore_ascii_case(&cte.name)) {
+                return Err(format!("WITH query name '{}' specified more than once", cte.name));
+            }
+            ctes.push(cte);
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        self.consume_token(Token::Keyword("SELECT".to_string()))?;
+        let mut query = self.handle_select()?;
+        query.with = Some(WithClause { recursive, ctes });
+        Ok(query)
+    }
+
+    fn parse_common_table_expression(&mut self, recursive: bool) -> Result<CommonTableExpression, String> {
+        let name = if let Token::Identifier(name) = self.advance() {
+            name
+        } else {
+            return Err("Expected WITH query name".to_string());
+        };
+
+        let columns = if self.peek() == &Token::Delimiter('(') {
+            self.parse_name_list("the WITH column list")?
+        } else {
+            Vec::new()
+        };
+        self.consume_token(Token::Keyword("AS".to_string()))?;
+        let query = self.parse_subquery()?;
+
+        let is_recursive = query.reads_table(&name)
+            || query.set_operations.iter().any(|operation| operation.query.reads_table(&name));
+        if is_recursive {
+            if !recursive {
+                return Err(format!("WITH query '{}' refers to itself; use WITH RECURSIVE", name));
+            }
+            if query.reads_table(&name) {
+                return Err(format!("The first member of recursive query '{}' must not refer to itself", name));
+            }
+            let unions_only = query.set_operations.iter().all(|operation| matches!(operation.operator, SetOperator::Union));
+            if query.set_operations.is_empty() || !unions_only {
+                return Err(format!("Recursive query '{}' must have the form anchor UNION [ALL] recursive member", name));
+            }
+        }
+
+        Ok(CommonTableExpression {
+            name,
+            columns,
+            query,
+        })
+    }
+
+    // A SELECT up to and including HAVING; ORDER BY and LIMIT apply to the
+    // whole compound query and are parsed by handle_select.
+    fn parse_select_core(&mut self) -> Result<SelectQuery, String> {
+        let distinct = self.check_keyword("DISTINCT");
+        if distinct {
+            self.advance();
+        }
+        let columns = self.with_windows(true, Self::parse_select_list)?;
+
+        let (from, joins) = if self.check_keyword("FROM") {
+            self.advance();
+            (Some(self.parse_table_source()?), self.parse_joins()?)
+        } else if columns.iter().any(|item| matches!(item, SelectItem::Wildcard)) {
+            return Err("SELECT * needs a FROM clause".to_string());
+        } else {
+            (None, Vec::new())
+        };
+
+        let where_clause = self.parse_where_clause()?;
+        let group_by = self.parse_group_by()?;
+        let having = if self.check_keyword("HAVING") {
+            self.advance();
+            Some(self.parse_condition()?)
+        } else {
+            None
+        };
+
+        Ok(SelectQuery {
+            with: None,
+            distinct,
+            selected_columns: columns,
+            from,
+            joins,
+            where_clause,
+            group_by,
+            having,
+            set_operations: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: 0,
+        })
+    }
+
+    // PRAGMA f(args) is shorthand for SELECT * FROM f(args).
+    fn handle_pragma(&mut self) -> Result<SelectQuery, String> {
+        let from = match self.parse_table_source()? {
+            TableSource::Function(function) => Some(TableSource::Function(function)),
+            TableSource::Table(table) | TableSource::SystemTime(SystemTimeTable { table, .. }) => {
+                return Err(format!("Unknown pragma: {}", table.name));
+            }
+        };
+
+        Ok(SelectQuery {
+            with: None,
+            distinct: false,
+            selected_columns: vec![SelectItem::Wildcard],
+            from,
+            joins: Vec::new(),
+            where_clause: None,
+            group_by: Vec::new(),
+            having: None,
+            set_operations: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: 0,
+        })
+    }
+
+    fn parse_group_by(&mut self) -> Result<Vec<Expression>, String> {
+        if !self.check_keyword("GROUP") {
+            return Ok(Vec::new());
+        }
+        self.advance();
+        self.consume_token(Token::Keyword("BY".to_string()))?;
+
+        let mut keys = Vec::new();
+        loop {
+            let key = self.parse_expression()?;
+            if key.contains_aggregate() {
+                return Err("Aggregate functions are not allowed in GROUP BY".to_string());
+            }
+            keys.push(key);
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
+    fn handle_insert(&mut self) -> Result<InsertQuery, String>{
+        self.consume_token(Token::Keyword("INTO".to_string()))?;
+        let table = self.parse_table()?;
+
+        self.consume_token(Token::Delimiter('('))?;
+        let columns = self.parse_column_list()?;
+        self.consume_token(Token::Delimiter(')'))?;
+
+        let source = self.parse_insert_source(columns.len())?;
+        let on_conflict = self.parse_on_conflict()?;
+
+        Ok(InsertQuery {
+            table_name: table,
+            columns,
+            source,
+            on_conflict,
+        })
+    }
+
+    fn parse_insert_source(&mut self, column_count: usize) -> Result<InsertSource, String> {
+        if self.check_keyword("SELECT") {
+            self.advance();
+            let query = self.handle_select()?;
+            let has_wildcard = query.selected_columns.iter().any(|item| matches!(item, SelectItem::Wildcard));
+            if !has_wildcard && query.selected_columns.len() != column_count {
+                return Err(format!(
+                    "INSERT ... SELECT selects {} column(s) for {} column(s)",
+                    query.selected_columns.len(),
+                    column_count
+                ));
+            }
+            return Ok(InsertSource::Select(Box::new(query)));
+        }
+
+        self.consume_token(Token::Keyword("VALUES".to_string()))?;
+        let mut rows = Vec::new();
+        loop {
+            self.consume_token(Token::Delimiter('('))?;
+            let values = self.parse_value_list()?;
+            self.consume_token(Token::Delimiter(')'))?;
+            if values.len() != column_count {
+                return Err(format!(
+                    "VALUES tuple {} has {} value(s) for {} column(s)",
+                    rows.len() + 1,
+                    values.len(),
+                    column_count
+                ));
+            }
+            rows.push(values);
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(InsertSource::Values(rows))
+    }
+
+    fn parse_on_conflict(&mut self) -> Result<Option<OnConflict>, String> {
+        if !self.check_keyword("ON") {
+            return Ok(None);
+        }
+        self.advance();
+        self.consume_word("CONFLICT")?;
+
+        let target = if self.peek() == &Token::Delimiter('(') {
+            self.parse_name_list("ON CONFLICT")?
+        } else {
+            Vec::new()
+        };
+
+        self.consume_word("DO")?;
+        let action = if self.check_word("NOTHING") {
+            self.advance();
+            ConflictAction::Nothing
+        } else if self.check_keyword("UPDATE") {
+            self.advance();
+            if target.is_empty() {
+                return Err("ON CONFLICT DO UPDATE requires a conflict target, e.g. ON CONFLICT (id)".to_string());
+            }
+            self.consume_token(Token::Keyword("SET".to_string()))?;
+            let changes = self.parse_set_list()?;
+            let where_clause = self.parse_where_clause()?;
+            ConflictAction::Update { changes, where_clause }
+        } else {
+            return Err(format!("Expected NOTHING or UPDATE after ON CONFLICT DO, found {:?}", self.peek()));
+        };
+
+        Ok(Some(OnConflict { target, action }))
+    }
+
+    fn handle_update(&mut self) -> Result<UpdateQuery, String>{
+        let table = self.parse_table()?;
+
+        self.consume_token(Token::Keyword("SET".to_string()))?;
+        let update_changes = self.parse_set_list()?;
+
+        let where_clause = self.parse_where_clause()?;
+    
+        Ok(UpdateQuery {
+            table_name: table,
+            changes: update_changes,
+            where_clause,
+        })
+    }
+
+    fn handle_delete(&mut self) -> Result<DeleteQuery, String> {
+        self.consume_token(Token::Keyword("FROM".to_string()))?;
+        let table = self.parse_table()?;
+        
+        let where_clause = self.parse_where_clause()?;
+        
+        Ok(DeleteQuery {
+            table_name: table,
+            where_clause,
+        })
+    }
+
+    fn handle_create(&mut self) -> Result<Query, String> {
+        if self.check_keyword("TABLE") {
+            return Ok(Query::CreateTable(self.handle_create_table()?));
+        }
+        match self.peek() {
+            Token::Identifier(word) if word.eq_ignore_ascii_case("JOB") => {
+                self.advance();
+                Ok(Query::CreateJob(self.handle_create_job()?))
+            }
+            token => Err(format!("Expected TABLE or JOB after CREATE, found {:?}", token)),
+        }
+    }
+
+    fn handle_create_job(&mut self) -> Result<CreateJobQuery, String> {
+        let name = if let Token::Identifier(name) = self.advance() {
+            name
+        } else {
+            return Err("Expected job name".to_string());
+        };
+
+        self.consume_word("SCHEDULE")?;
+        let schedule = if let Token::StringLiteral(expression) = self.advance() {
+            Schedule::parse(&expression)?
+        } else {
+            return Err("SCHEDULE expects a cron expression string".to_string());
+        };
+
+        self.consume_token(Token::Keyword("AS".to_string()))?;
+        let statement = self.parse_statement()?;
+        if matches!(statement, Query::CreateJob(_) | Query::Set(_)) {
+            return Err(format!("{} cannot be scheduled as a job", statement.kind()));
+        }
+
+        Ok(CreateJobQuery {
+            name,
+            schedule,
+            statement: Box::new(statement),
+        })
+    }
+
+    fn handle_create_table(&mut self) -> Result<CreateTableQuery, String> {
+        self.consume_token(Token::Keyword("TABLE".to_string()))?;
+        let table = self.parse_table()?;
+
+        self.consume_token(Token::Delimiter('('))?;
+        let mut columns = Vec::new();
+        let mut declared = Vec::new();
+        loop {
+            if self.is_table_constraint_start() {
+                declared.push(self.parse_table_constraint()?);
+            } else {
+                columns.push(self.parse_column_definition(&mut declared)?);
+            }
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume_token(Token::Delimiter(')'))?;
+
+        let mut row_version = false;
+        let mut system_versioned = false;
+        if self.check_keyword("WITH") {
+            self.advance();
+            loop {
+                match self.advance() {
+                    Token::Identifier(ref word) if word.eq_ignore_ascii_case("ROW") => {
+                        self.consume_word("VERSION")?;
+                        row_version = true;
+                    }
+                    Token::Identifier(ref word) if word.eq_ignore_ascii_case("SYSTEM") => {
+                        self.consume_word("VERSIONING")?;
+                        system_versioned = true;
+                    }
+                    token => return Err(format!("Expected ROW VERSION or SYSTEM VERSIONING, found {:?}", token)),
+                }
+                if self.peek() == &Token::Delimiter(',') {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let constraints = name_constraints(&table.name, declared)?;
+        let mut query = CreateTableQuery {
+            table_name: table,
+            columns,
+            constraints,
+            row_version,
+            system_versioned,
+        };
+
+        let key_columns: Vec<String> = query
+            .constraints
+            .iter()
+            .filter_map(|constraint| match &constraint.kind {
+                ConstraintKind::PrimaryKey(columns) => Some(columns.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        for definition in &mut query.columns {
+            if key_columns.iter().any(|name| name.eq_ignore_ascii_case(&definition.column.name)) {
+                definition.not_null = true;
+            }
+        }
+        query.validate_constraints()?;
+        Ok(query)
+    }
+
+    fn is_table_constraint_start(&self) -> bool {
+        let next = self.tokens.get(self.position + 1);
+        self.check_word("CONSTRAINT")
+            || (self.check_word("PRIMARY") || self.check_word("FOREIGN"))
+                && matches!(next, Some(Token::Identifier(word)) if word.eq_ignore_ascii_case("KEY"))
+            || (self.check_word("UNIQUE") || self.check_word("CHECK")) && next == Some(&Token::Delimiter('('))
+    }
+
+    fn parse_constraint_name(&mut self) -> Result<Option<String>, String> {
+        if !self.check_word("CONSTRAINT") {
+            return Ok(None);
+        }
+        self.advance();
+        match self.advance() {
+            Token::Identifier(name) => Ok(Some(name)),
+            token => Err(format!("Expected constraint name after CONSTRAINT, found {:?}", token)),
+        }
+    }
+
+    // PRIMARY KEY (a, b), UNIQUE (a, b), CHECK (condition) or FOREIGN KEY (a, b) REFERENCES ...,
+    // optionally named with CONSTRAINT name.
+    fn parse_table_constraint(&mut self) -> Result<(Option<String>, ConstraintKind), String> {
+        let name = self.parse_constraint_name()?;
+        let kind = if self.check_word("PRIMARY") {
+            self.advance();
+            self.consume_word("KEY")?;
+            ConstraintKind::PrimaryKey(self.parse_name_list("PRIMARY KEY")?)
+        } else if self.check_word("UNIQUE") {
+            self.advance();
+            ConstraintKind::Unique(self.parse_name_list("UNIQUE")?)
+        } else if self.check_word("CHECK") {
+            self.advance();
+            ConstraintKind::Check(self.parse_check_condition()?)
+        } else if self.check_word("FOREIGN") {
+            self.advance();
+            self.consume_word("KEY")?;
+            let columns = self.parse_name_list("FOREIGN KEY")?;
+            ConstraintKind::ForeignKey(self.parse_references(columns)?)
+        } else {
+            return Err(format!("Expected PRIMARY KEY, UNIQUE, CHECK or FOREIGN KEY, found {:?}", self.peek()));
+        };
+        Ok((name, kind))
+    }
+
+    fn parse_check_condition(&mut self) -> Result<Condition, String> {
+        self.consume_token(Token::Delimiter('('))?;
+        let condition = self.parse_condition()?;
+        self.consume_token(Token::Delimiter(')'))?;
+
+        let mut subqueries = Vec::new();
+        condition.collect_subqueries(&mut subqueries);
+        if !subqueries.is_empty() || condition.contains_aggregate() {
+            return Err("CHECK constraints cannot use subqueries or aggregates".to_string());
+        }
+        Ok(condition)
+    }
+
+    // REFERENCES parent [(a, b)] [ON DELETE RESTRICT | CASCADE | SET NULL]
+    fn parse_references(&mut self, columns: Vec<String>) -> Result<ForeignKey, String> {
+        self.consume_word("REFERENCES")?;
+        let parent = self.parse_table()?;
+        let parent_columns = if self.peek() == &Token::Delimiter('(') {
+            let parent_columns = self.parse_name_list("REFERENCES")?;
+            if parent_columns.len() != columns.len() {
+                return Err(format!(
+                    "Foreign key has {} column(s) but references {} column(s) of '{}'",
+                    columns.len(),
+                    parent_columns.len(),
+                    parent.name
+                ));
+            }
+            Some(parent_columns)
+        } else {
+            None
+        };
+
+        let mut on_delete = ReferentialAction::Restrict;
+        if self.check_keyword("ON") {
+            self.advance();
+            self.consume_token(Token::Keyword("DELETE".to_string()))?;
+            on_delete = if self.check_word("RESTRICT") {
+                self.advance();
+                ReferentialAction::Restrict
+            } else if self.check_word("CASCADE") {
+                self.advance();
+                ReferentialAction::Cascade
+            } else if self.check_keyword("SET") {
+                self.advance();
+                self.consume_token(Token::Keyword("NULL".to_string()))?;
+                ReferentialAction::SetNull
+            } else {
+                return Err(format!("Expected RESTRICT, CASCADE or SET NULL after ON DELETE, found {:?}", self.peek()));
+            };
+        }
+
+        Ok(ForeignKey {
+            columns,
+            parent,
+            parent_columns,
+            on_delete,
+        })
+    }
+
+    // Constraints declared on the column are added to the table's constraints.
+    fn parse_column_definition(&mut self, constraints: &mut Vec<(Option<String>, ConstraintKind)>) -> Result<ColumnDefinition, String> {
+        let column = self.parse_column()?;
+        if column.name.eq_ignore_ascii_case(ROW_VERSION_COLUMN) {
+            return Err(format!("Column name '{}' is reserved for the row version", ROW_VERSION_COLUMN));
+        }
+        let data_type = self.parse_data_type()?;
+
+        let mut compressed = false;
+        // Some(true) after NOT NULL, Some(false) after NULL.
+        let mut not_null = None;
+        let mut default = None;
+        loop {
+            let name = self.parse_constraint_name()?;
+            let kind = if self.check_word("PRIMARY") {
+                self.advance();
+                self.consume_word("KEY")?;
+                ConstraintKind::PrimaryKey(vec![column.name.clone()])
+            } else if self.check_word("UNIQUE") {
+                self.advance();
+                ConstraintKind::Unique(vec![column.name.clone()])
+            } else if self.check_word("CHECK") {
+                self.advance();
+                ConstraintKind::Check(self.parse_check_condition()?)
+            } else if self.check_word("REFERENCES") {
+                ConstraintKind::ForeignKey(self.parse_references(vec![column.name.clone()])?)
+            } else if self.check_keyword("NOT") || self.check_keyword("NULL") {
+                let declared = self.check_keyword("NOT");
+                if declared {
+                    self.advance();
+                }
+                self.consume_token(Token::Keyword("NULL".to_string()))?;
+                if not_null.is_some_and(|existing| existing != declared) {
+                    return Err(format!("Column '{}' cannot be both NULL and NOT NULL", column.name));
+                }
+                not_null = Some(declared);
+                continue;
+            } else if self.check_word("DEFAULT") {
+                self.advance();
+                if default.is_some() {
+                    return Err(format!("Column '{}' has more than one DEFAULT", column.name));
+                }
+                default = Some(self.parse_column_default(&column, &data_type)?);
+                continue;
+            } else if name.is_some() {
+                return Err(format!("Expected a constraint after the name, found {:?}", self.peek()));
+            } else if self.check_word("COMPRESSED") {
+                self.advance();
+                if !matches!(data_type, DataType::Text | DataType::Blob) {
+                    return Err(format!("Column '{}' cannot be COMPRESSED, only TEXT and BLOB columns can", column.name));
+                }
+                compressed = true;
+                continue;
+            } else {
+                break;
+            };
+            constraints.push((name, kind));
+        }
+
+        let not_null = not_null.unwrap_or(false);
+        if not_null && matches!(default, Some(Expression::Value(Value::Null))) {
+            return Err(format!("Column '{}' is NOT NULL but its DEFAULT is NULL", column.name));
+        }
+        Ok(ColumnDefinition {
+            column,
+            data_type,
+            compressed,
+            not_null,
+            default,
+        })
+    }
+
+    fn parse_column_default(&mut self, column: &Column, data_type: &DataType) -> Result<Expression, String> {
+        let expression = self.parse_expression()?;
+        let mut columns = Vec::new();
+        expression.collect_bare_columns(&mut columns);
+        let mut subqueries = Vec::new();
+        expression.collect_subqueries(&mut subqueries);
+        if !columns.is_empty() || !subqueries.is_empty() || expression.contains_aggregate() {
+            return Err(format!(
+                "DEFAULT for column '{}' cannot refer to columns, subqueries or aggregates",
+                column.name
+            ));
+        }
+
+        // A literal default is converted to the column type now, so a bad default fails CREATE TABLE.
+        match expression {
+            Expression::Value(value) => Ok(Expression::Value(coercion::cast(value, data_type)?)),
+            expression => Ok(expression),
+        }
+    }
+
+    fn parse_data_type(&mut self) -> Result<DataType, String> {
+        if let Token::Identifier(name) = self.advance() {
+            match name.to_uppercase().as_str() {
+                "INTEGER" | "INT" => Ok(DataType::Integer),
+                "FLOAT" | "REAL" => Ok(DataType::Float),
+                "TEXT" => Ok(DataType::Text),
+                "BOOLEAN" | "BOOL" => Ok(DataType::Boolean),
+                "DATE" => Ok(DataType::Date),
+                "TIMESTAMP" => Ok(DataType::Timestamp),
+                "TIMESTAMPTZ" => Ok(DataType::TimestampTz),
+                "DECIMAL" | "NUMERIC" => self.parse_decimal_type(),
+                "BLOB" => Ok(DataType::Blob),
+                _ => Err(format!("Unknown data type: {}", name)),
+            }
+        } else {
+            Err("Expected data type".to_string())
+        }
+    }
+
+    fn parse_decimal_type(&mut self) -> Result<DataType, String> {
+        if self.peek() != &Token::Delimiter('(') {
+            return Ok(DataType::Decimal { precision: decimal::MAX_PRECISION, scale: 0 });
+        }
+        self.advance();
+
+        let precision = self.parse_type_modifier()?;
+        let scale = if self.peek() == &Token::Delimiter(',') {
+            self.advance();
+            self.parse_type_modifier()?
+        } else {
+            0
+        };
+        self.consume_token(Token::Delimiter(')'))?;
+
+        if precision == 0 || precision > decimal::MAX_PRECISION {
+            return Err(format!("DECIMAL precision must be between 1 and {}", decimal::MAX_PRECISION));
+        }
+        if scale > precision {
+            return Err("DECIMAL scale cannot exceed its precision".to_string());
+        }
+        Ok(DataType::Decimal { precision, scale })
+    }
+
+    fn parse_type_modifier(&mut self) -> Result<u32, String> {
+        match self.advance() {
+            Token::Number(value) if value >= 0 && value <= u32::MAX as i64 => Ok(value as u32),
+            _ => Err("Expected a non-negative integer type modifier".to_string()),
+        }
+    }
+
+    fn handle_rollback_to(&mut self) -> Result<SavepointQuery, String> {
+        self.consume_token(Token::Keyword("TO".to_string()))?;
+        if self.check_word("SAVEPOINT") {
+            self.advance();
+        }
+        self.parse_savepoint_name()
+    }
+
+    fn handle_release(&mut self) -> Result<SavepointQuery, String> {
+        if self.check_word("SAVEPOINT") {
+            self.advance();
+        }
+        self.parse_savepoint_name()
+    }
+
+    fn parse_savepoint_name(&mut self) -> Result<SavepointQuery, String> {
+        if let Token::Identifier(name) = self.advance() {
+            Ok(SavepointQuery { name })
+        } else {
+            Err("Expected savepoint name".to_string())
+        }
+    }
+
+    fn handle_create_snapshot(&mut self) -> Result<SnapshotQuery, String> {
+        self.consume_token(Token::Keyword("CREATE".to_string()))?;
+        self.parse_snapshot_name()
+    }
+
+    fn handle_attach(&mut self) -> Result<Query, String> {
+        if self.check_word("SNAPSHOT") {
+            self.advance();
+            return Ok(Query::AttachSnapshot(self.handle_attach_snapshot()?));
+        }
+
+        let path = if let Token::StringLiteral(path) = self.advance() {
+            path
+        } else {
+            return Err("Expected database file name or SNAPSHOT after ATTACH".to_string());
+        };
+
+        self.consume_token(Token::Keyword("AS".to_string()))?;
+        let alias = if let Token::Identifier(alias) = self.advance() {
+            alias
+        } else {
+            return Err("Expected database name after AS".to_string());
+        };
+
+        Ok(Query::Attach(AttachQuery {
+            path,
+            alias,
+        }))
+    }
+
+    fn handle_attach_snapshot(&mut self) -> Result<AttachSnapshotQuery, String> {
+        let snapshot = self.parse_snapshot_name()?;
+
+        let alias = if self.check_keyword("AS") {
+            self.advance();
+            if let Token::Identifier(alias) = self.advance() {
+                Some(alias)
+            } else {
+                return Err("Expected alias after AS".to_string());
+            }
+        } else {
+            None
+        };
+
+        Ok(AttachSnapshotQuery {
+            snapshot,
+            alias,
+        })
+    }
+
+    fn parse_snapshot_name(&mut self) -> Result<SnapshotQuery, String> {
+        if let Token::Identifier(name) = self.advance() {
+            Ok(SnapshotQuery { name })
+        } else {
+            Err("Expected snapshot name".to_string())
+        }
+    }
+
+    fn handle_declare_cursor(&mut self) -> Result<DeclareCursorQuery, String> {
+        let cursor = self.parse_cursor_name()?;
+        self.consume_word("CURSOR")?;
+        self.consume_token(Token::Keyword("FOR".to_string()))?;
+        self.consume_token(Token::Keyword("SELECT".to_string()))?;
+        let query = self.handle_select()?;
+
+        Ok(DeclareCursorQuery {
+            cursor,
+            query,
+        })
+    }
+
+    fn handle_fetch(&mut self) -> Result<FetchQuery, String> {
+        let count = match self.peek() {
+            Token::Number(count) if *count > 0 => {
+                let count = *count as u64;
+                self.advance();
+                count
+            }
+            Token::Number(_) => return Err("FETCH count must be positive".to_string()),
+            _ => 1,
+        };
+
+        self.consume_token(Token::Keyword("FROM".to_string()))?;
+        let cursor = self.parse_cursor_name()?;
+
+        Ok(FetchQuery {
+            cursor,
+            count,
+        })
+    }
+
+    fn parse_cursor_name(&mut self) -> Result<CursorQuery, String> {
+        if let Token::Identifier(name) = self.advance() {
+            Ok(CursorQuery { name })
+        } else {
+            Err("Expected cursor name".to_string())
+        }
+    }
+
+    fn handle_notify(&mut self) -> Result<NotifyQuery, String> {
+        let channel = self.parse_channel_name()?;
+
+        let payload = if self.peek() == &Token::Delimiter(',') {
+            self.advance();
+            if let Token::StringLiteral(payload) = self.advance() {
+                Some(payload)
+            } else {
+                return Err("NOTIFY payload must be a string literal".to_string());
+            }
+        } else {
+            None
+        };
+
+        Ok(NotifyQuery {
+            channel,
+            payload,
+        })
+    }
+
+    fn parse_channel_name(&mut self) -> Result<ChannelQuery, String> {
+        if let Token::Identifier(name) = self.advance() {
+            Ok(ChannelQuery { name })
+        } else {
+            Err("Expected channel name".to_string())
+        }
+    }
+
+    fn handle_set_statement(&mut self) -> Result<SetQuery, String> {
+        let name = if let Token::Identifier(name) = self.advance() {
+            name
+        } else {
+            return Err("Expected setting name".to_string());
+        };
+
+        match self.advance() {
+            Token::Operator(ref op) if op == "=" => {}
+            Token::Keyword(ref keyword) if keyword == "TO" => {}
+            _ => return Err("Expected '=' or TO after setting name".to_string()),
+        }
+
+        let value = if let Token::Identifier(word) = self.peek() {
+            let word = word.clone();
+            self.advance();
+            Value::Text(word)
+        } else {
+            self.parse_value()?
+        };
+
+        Ok(SetQuery {
+            name,
+            value,
+        })
+    }
+
+    fn parse_set_list(&mut self) -> Result<Vec<UpdateSet>, String>{
+        let mut changes = Vec::new();
+
+        loop {
+            let change = self.parse_set()?;
+            if changes.iter().any(|existing: &UpdateSet| existing.column.name.eq_ignore_ascii_case(&change.column.name)) {
+                return Err(format!("Column '{}' is assigned more than once in SET", change.column.name));
+            }
+            changes.push(change);
+            if self.peek() == &Token::Delimiter(','){
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(changes)
+    }
+
+    fn parse_set(&mut self) -> Result<UpdateSet, String>{
+        let column = self.parse_column()?;
+        if column.name.eq_ignore_ascii_case(ROW_VERSION_COLUMN) {
+            return Err(format!("{} is maintained by the engine and cannot be assigned", ROW_VERSION_COLUMN));
+        }
+
+        if let Token::Operator(op) = self.advance() {
+            if op != "=" {
+                return Err("Expected '=' in SET clause".to_string());
+            }
+        } else {
+            return Err("Expected '=' operator in SET clause".to_string());
+        }
+
+        // The new value is computed from the row's current values, e.g. counter = counter + 1.
+        let value = self.parse_function_argument()?;
+        if value.contains_aggregate() {
+            return Err("Aggregate functions are not allowed in SET".to_string());
+        }
+
+        Ok(UpdateSet{
+            column,
+            value,
+        })      
+    }
+
+    fn parse_value_list(&mut self) -> Result<Vec<Value>, String> {
+        let mut values = Vec::new();
+
+        loop {
+            values.push(self.parse_value()?);
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance(); 
+            } else {
+                break;
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String>{
+        let token = self.advance();
+        match token{
+            Token::Operator(ref op) if op == "-" => negate_value(self.parse_value()?),
+            Token::Float(value) => Ok(Value::Float(value)),
+            Token::Number(value) => Ok(Value::Integer(value)),
+            Token::StringLiteral(text) => Ok(Value::Text(text)),
+            Token::Boolean(boolean) => Ok(Value::Boolean(boolean)),
+            Token::BlobLiteral(bytes) => Ok(Value::Blob(bytes)),
+            Token::Keyword(ref keyword) if keyword == "NULL" => Ok(Value::Null),
+            Token::Identifier(ref name) if self.is_typed_literal(name) => self.parse_typed_literal(name),
+            _ => Err("Expected value".to_string()),
+        }
+    }
+
+    fn is_typed_literal(&self, type_name: &str) -> bool {
+        let type_name = type_name.to_uppercase();
+        ["DATE", "TIMESTAMP", "TIMESTAMPTZ", "DECIMAL"].contains(&type_name.as_str())
+            && matches!(self.peek(), Token::StringLiteral(_))
+    }
+
+    fn parse_typed_literal(&mut self, type_name: &str) -> Result<Value, String> {
+        let text = if let Token::StringLiteral(text) = self.advance() {
+            text
+        } else {
+            return Err(format!("Expected string literal after {}", type_name));
+        };
+
+        match type_name.to_uppercase().as_str() {
+            "DATE" => Ok(Value::Date(Date::parse(&text)?)),
+            "TIMESTAMP" => Ok(Value::Timestamp(Timestamp::parse(&text)?)),
+            "TIMESTAMPTZ" => Ok(Value::TimestampTz(Timestamp::parse_with_zone(&text)?)),
+            "DECIMAL" => Ok(Value::Decimal(Decimal::parse(&text)?)),
+            _ => Err(format!("Unknown literal type: {}", type_name)),
+        }
+    }
+
+    fn parse_select_list(&mut self) -> Result<Vec<SelectItem>, String> {
+        let mut items = Vec::new();
+
+        loop {
+            if self.peek() == &Token::Operator("*".to_string()) {
+                self.advance();
+                items.push(SelectItem::Wildcard);
+            } else {
+                let expression = self.parse_expression()?;
+                let alias = self.parse_alias()?;
+                items.push(SelectItem::Expression(expression, alias));
+            }
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn parse_alias(&mut self) -> Result<Option<String>, String> {
+        if self.check_keyword("AS") {
+            self.advance();
+            if let Token::Identifier(alias) = self.advance() {
+                return Ok(Some(alias));
+            }
+            return Err("Expected alias after AS".to_string());
+        }
+
+        if let Token::Identifier(alias) = self.peek() {
+            let alias = alias.clone();
+            self.advance();
+            Ok(Some(alias))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_column_list(&mut self) -> Result<Vec<Column>, String> {
+        let mut columns = Vec::new();
+
+        loop {
+            columns.push(self.parse_column()?);
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance(); 
+            } else {
+                break;
+            }
+        }
+
+        Ok(columns)
+    }
+
+    fn parse_column(&mut self) -> Result<Column, String> {
+        if let Token::Identifier(name) = self.advance() {
+            Ok(Column { name, table: None })
+        } else {
+            Err("Expected column name".to_string())
+        }
+    }
+
+    fn parse_column_reference(&mut self, name: String) -> Result<Column, String> {
+        if self.peek() != &Token::Delimiter('.') {
+            return Ok(Column { name, table: None });
+        }
+        self.advance();
+
+        if let Token::Identifier(column) = self.advance() {
+            Ok(Column { name: column, table: Some(name) })
+        } else {
+            Err(format!("Expected column name after '{}.'", name))
+        }
+    }
+
+    fn parse_joins(&mut self) -> Result<Vec<Join>, String> {
+        let mut joins = Vec::new();
+        loop {
+            // 'FROM a, b' is the old-style spelling of 'FROM a CROSS JOIN b'.
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+                joins.push(Join {
+                    kind: JoinKind::Cross,
+                    source: self.parse_table_source()?,
+                    constraint: JoinConstraint::None,
+                });
+                continue;
+            }
+
+            let natural = self.check_keyword("NATURAL");
+            if natural {
+                self.advance();
+            }
+            let kind = match self.peek() {
+                Token::Keyword(keyword) if keyword == "JOIN" => JoinKind::Inner,
+                Token::Keyword(keyword) if keyword == "INNER" => JoinKind::Inner,
+                Token::Keyword(keyword) if keyword == "LEFT" => JoinKind::Left,
+                Token::Keyword(keyword) if keyword == "RIGHT" => JoinKind::Right,
+                Token::Keyword(keyword) if keyword == "FULL" => JoinKind::Full,
+                Token::Keyword(keyword) if keyword == "CROSS" && !natural => JoinKind::Cross,
+                token if natural => return Err(format!("Expected JOIN after NATURAL, found {:?}", token)),
+                _ => break,
+            };
+            if !self.check_keyword("JOIN") {
+                let is_outer = matches!(kind, JoinKind::Left | JoinKind::Right | JoinKind::Full);
+                self.advance();
+                if is_outer && self.check_keyword("OUTER") {
+                    self.advance();
+                }
+            }
+            self.consume_token(Token::Keyword("JOIN".to_string()))?;
+            let source = self.parse_table_source()?;
+
+            let constraint = if natural {
+                JoinConstraint::Natural
+            } else if matches!(kind, JoinKind::Cross) {
+                JoinConstraint::None
+            } else if self.check_keyword("USING") {
+                self.advance();
+                JoinConstraint::Using(self.parse_name_list("USING")?)
+            } else {
+                self.consume_token(Token::Keyword("ON".to_string()))?;
+                let condition = self.parse_condition()?;
+                if condition.contains_aggregate() {
+                    return Err("Aggregate functions are not allowed in JOIN conditions".to_string());
+                }
+                JoinConstraint::On(condition)
+            };
+
+            joins.push(Join {
+                kind,
+                source,
+                constraint,
+            });
+        }
+        Ok(joins)
+    }
+
+    fn parse_name_list(&mut self, context: &str) -> Result<Vec<String>, String> {
+        self.consume_token(Token::Delimiter('('))?;
+        let mut columns: Vec<String> = Vec::new();
+        loop {
+            let column = self.parse_column()?.name;
+            if columns.iter().any(|existing| existing.eq_ignore_ascii_case(&column)) {
+                return Err(format!("Column '{}' appears more than once in {}", column, context));
+            }
+            columns.push(column);
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume_token(Token::Delimiter(')'))?;
+        Ok(columns)
+    }
+
+    fn parse_table_source(&mut self) -> Result<TableSource, String> {
+        let is_function = matches!(self.peek(), Token::Identifier(_))
+            && self.tokens.get(self.position + 1) == Some(&Token::Delimiter('('));
+        if !is_function {
+            let mut table = self.parse_table()?;
+            let as_of = self.parse_system_time()?;
+            table.alias = self.parse_alias()?;
+            return Ok(match as_of {
+                Some(as_of) => TableSource::SystemTime(SystemTimeTable { table, as_of }),
+                None => TableSource::Table(table),
+            });
+        }
+
+        let name = if let Token::Identifier(name) = self.advance() {
+            name
+        } else {
+            return Err("Expected table function name".to_string());
+        };
+        let function = match functions::lookup_table_function(&name) {
+            Some(function) => function,
+            None => return Err(format!("Unknown table function: {}", name)),
+        };
+
+        self.consume_token(Token::Delimiter('('))?;
+        let arguments = if self.peek() == &Token::Delimiter(')') {
+            Vec::new()
+        } else {
+            self.parse_value_list()?
+        };
+        self.consume_token(Token::Delimiter(')'))?;
+        if arguments.len() != function.arguments {
+            return Err(format!("{} expects {} argument(s), got {}", function.name, function.arguments, arguments.len()));
+        }
+
+        Ok(TableSource::Function(TableFunctionCall {
+            name: function.name.to_lowercase(),
+            arguments,
+            alias: self.parse_alias()?,
+        }))
+    }
+
+    fn parse_system_time(&mut self) -> Result<Option<Value>, String> {
+        if !self.check_keyword("FOR") {
+            return Ok(None);
+        }
+        self.advance();
+        self.consume_word("SYSTEM_TIME")?;
+        self.consume_token(Token::Keyword("AS".to_string()))?;
+        self.consume_word("OF")?;
+
+        match self.parse_value()? {
+            value @ (Value::Timestamp(_) | Value::TimestampTz(_)) => Ok(Some(value)),
+            value => Err(format!("FOR SYSTEM_TIME AS OF expects a timestamp, found {:?}", value)),
+        }
+    }
+
+    fn parse_table(&mut self) -> Result<Table, String> {
+        let name = if let Token::Identifier(name) = self.advance() {
+            name
+        } else {
+            return Err("Expected table name".to_string());
+        };
+
+        if self.peek() == &Token::Delimiter('.') {
+            self.advance();
+            if let Token::Identifier(table) = self.advance() {
+                Ok(Table { name: table, database: Some(name), alias: None })
+            } else {
+                Err("Expected table name after database name".to_string())
+            }
+        } else {
+            Ok(Table { name, database: None, alias: None })
+        }
+    }
+
+    fn parse_order_by(&mut self) -> Result<Vec<OrderByItem>, String> {
+        if !self.check_keyword("ORDER") {
+            return Ok(Vec::new());
+        }
+        self.advance();
+        self.consume_token(Token::Keyword("BY".to_string()))?;
+
+        let mut items = Vec::new();
+        loop {
+            let expression = self.parse_expression()?;
+            let descending = if self.check_keyword("DESC") {
+                self.advance();
+                true
+            } else {
+                if self.check_keyword("ASC") {
+                    self.advance();
+                }
+                false
+            };
+            let nulls = if matches!(self.peek(), Token::Identifier(word) if word.eq_ignore_ascii_case("NULLS")) {
+                self.advance();
+                match self.advance() {
+                    Token::Identifier(ref word) if word.eq_ignore_ascii_case("FIRST") => Some(NullsOrder::First),
+                    Token::Identifier(ref word) if word.eq_ignore_ascii_case("LAST") => Some(NullsOrder::Last),
+                    token => return Err(format!("Expected FIRST or LAST after NULLS, found {:?}", token)),
+                }
+            } else {
+                None
+            };
+
+            items.push(OrderByItem {
+                expression,
+                descending,
+                nulls,
+            });
+            if self.peek() == &Token::Delimiter(',') {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn parse_limit(&mut self) -> Result<(Option<u64>, u64), String> {
+        if !self.check_keyword("LIMIT") {
+            return Ok((None, 0));
+        }
+        self.advance();
+        let limit = self.parse_row_count("LIMIT")?;
+
+        let offset = if self.check_keyword("OFFSET") {
+            self.advance();
+            self.parse_row_count("OFFSET")?
+        } else {
+            0
+        };
+        Ok((Some(limit), offset))
+    }
+
+    fn parse_row_count(&mut self, clause: &str) -> Result<u64, String> {
+        match self.advance() {
+            Token::Number(count) if count >= 0 => Ok(count as u64),
+            _ => Err(format!("{} expects a non-negative integer", clause)),
+        }
+    }
+
+    fn parse_where_clause(&mut self) -> Result<Option<Condition>, String> {
+        if !self.check_keyword("WHERE") {
+            return Ok(None);
+        }
+        self.advance();
+
+        let condition = self.parse_condition()?;
+        if condition.contains_aggregate() {
+            return Err("Aggregate functions are not allowed in WHERE".to_string());
+        }
+        Ok(Some(condition))
+    }
+
+    fn parse_condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.parse_and_condition()?;
+        while self.check_keyword("OR") {
+            self.advance();
+            let right = self.parse_and_condition()?;
+            condition = Condition::Or(Box::new(condition), Box::new(right));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and_condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.parse_not_condition()?;
+        while self.check_keyword("AND") {
+            self.advance();
+            let right = self.parse_not_condition()?;
+            condition = Condition::And(Box::new(condition), Box::new(right));
+        }
+        Ok(condition)
+    }
+
+    fn parse_not_condition(&mut self) -> Result<Condition, String> {
+        if self.check_keyword("NOT") {
+            self.advance();
+            return Ok(Condition::Not(Box::new(self.parse_not_condition()?)));
+        }
+        if self.check_keyword("EXISTS") {
+            self.advance();
+            if !self.is_subquery_start() {
+                return Err("EXISTS expects a parenthesized SELECT".to_string());
+            }
+            return Ok(Condition::Exists(Box::new(self.parse_subquery()?)));
+        }
+        // A '(' may open either a grouped condition or an expression such as
+        // '(a + 1) > 2'. The group is parsed once as a condition; if an operator
+        // follows, it must have been a plain expression and parsing resumes from it.
+        if self.peek() == &Token::Delimiter('(') && !self.is_subquery_start() {
+            self.advance();
+            let condition = self.parse_condition()?;
+            self.consume_token(Token::Delimiter(')'))?;
+            if !self.continues_predicate() && !self.check_keyword("AT") {
+                return Ok(condition);
+            }
+            let expression = match condition {
+                Condition::Expression(expression) => expression,
+                _ => return Err(format!("Unexpected {:?} after a parenthesized condition", self.peek())),
+            };
+            let expression = self.continue_postfix_expression(expression)?;
+            let expression = self.continue_binary_expression(expression, 0)?;
+            return self.continue_predicate(expression);
+        }
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> Result<Condition, String> {
+        let left = self.parse_expression()?;
+        self.continue_predicate(left)
+    }
+
+    fn continue_predicate(&mut self, left: Expression) -> Result<Condition, String> {
+        if self.check_keyword("IS") {
+            self.advance();
+            let negated = if self.check_keyword("NOT") {
+                self.advance();
+                true
+            } else {
+                false
+            };
+            self.consume_token(Token::Keyword("NULL".to_string()))?;
+            return Ok(Condition::IsNull(IsNullCondition {
+                expression: left,
+                negated,
+            }));
+        }
+
+        let negated = if self.check_keyword("NOT") {
+            self.advance();
+            true
+        } else {
+            false
+        };
+        if self.check_keyword("IN") {
+            self.advance();
+            let list = if self.is_subquery_start() {
+                InList::Subquery(Box::new(self.parse_single_column_subquery("Subquery in IN")?))
+            } else {
+                self.consume_token(Token::Delimiter('('))?;
+                let values = self.parse_value_list()?;
+                self.consume_token(Token::Delimiter(')'))?;
+                InList::Values(values)
+            };
+            return Ok(Condition::In(InCondition {
+                expression: left,
+                list,
+                negated,
+            }));
+        }
+        if self.check_keyword("LIKE") {
+            self.advance();
+            return Ok(Condition::Like(LikeCondition {
+                expression: left,
+                pattern: self.parse_like_pattern()?,
+                negated,
+            }));
+        }
+        if negated {
+            return Err(format!("Expected IN or LIKE after NOT, found {:?}", self.peek()));
+        }
 
-    fn handle_update(&mut self) -> Result<UpdateQuery, String>{
-        let table = self.parse_table()?;
+        if !matches!(self.peek(), Token::Operator(_)) {
+            return Ok(Condition::Expression(left));
+        }
 
-        self.consume_token(Token::Keyword("SET".to_string()))?;
-        let update_changes = self.parse_set_list()?;
+        let operator = self.parse_operator()?;
+        let right = self.parse_expression()?;
+        if let (Some(left_type), Some(right_type)) = (left.static_type(), right.static_type()) {
+            if coercion::common_type(&left_type, &right_type).is_none() {
+                return Err(format!(
+                    "Cannot compare {} with {}, use CAST to convert one side",
+                    coercion::type_name(&left_type),
+                    coercion::type_name(&right_type)
+                ));
+            }
+        }
 
-        let where_clause = if self.check_keyword("WHERE") {
-            self.advance();
-            Some(self.parse_condition()?)
+        Ok(Condition::Comparison(Comparison {
+            left,
+            operator,
+            right,
+        }))
+    }
+
+    fn parse_like_pattern(&mut self) -> Result<LikePattern, String> {
+        let pattern = if let Token::StringLiteral(pattern) = self.advance() {
+            pattern
         } else {
-            None
+            return Err("LIKE pattern must be a string literal".to_string());
         };
-    
-        Ok(UpdateQuery {
-            table_name: table,
-            changes: update_changes,
-            where_clause,
-        })
-    }
 
-    fn handle_delete(&mut self) -> Result<DeleteQuery, String> {
-        self.consume_token(Token::Keyword("FROM".to_string()))?;
-        let table = self.parse_table()?;
-        
-        let where_clause = if self.check_keyword("WHERE") {
+        let escape = if self.check_keyword("ESCAPE") {
             self.advance();
-            Some(self.parse_condition()?)
+            match self.advance() {
+                Token::StringLiteral(escape) if escape.chars().count() == 1 => escape.chars().next(),
+                _ => return Err("ESCAPE must be a single-character string literal".to_string()),
+            }
         } else {
             None
         };
-        
-        Ok(DeleteQuery {
-            table_name: table,
-            where_clause,
-        })
+
+        LikePattern::compile(&pattern, escape)
     }
 
-    fn parse_set_list(&mut self) -> Result<Vec<UpdateSet>, String>{
-        let mut changes = Vec::new();
+    fn is_subquery_start(&self) -> bool {
+        self.peek() == &Token::Delimiter('(')
+            && self.tokens.get(self.position + 1) == Some(&Token::Keyword("SELECT".to_string()))
+    }
 
-        loop {
-            changes.push(self.parse_set()?);
-            if self.peek() == &Token::Delimiter(','){
-                self.advance();
-            } else {
+    fn parse_subquery(&mut self) -> Result<SelectQuery, String> {
+        self.consume_token(Token::Delimiter('('))?;
+        self.consume_token(Token::Keyword("SELECT".to_string()))?;
+        let query = self.with_windows(false, Self::handle_select)?;
+        self.consume_token(Token::Delimiter(')'))?;
+        Ok(query)
+    }
+
+    fn parse_single_column_subquery(&mut self, context: &str) -> Result<SelectQuery, String> {
+        let query = self.parse_subquery()?;
+        if query.selected_columns.len() != 1 || matches!(query.selected_columns[0], SelectItem::Wildcard) {
+            return Err(format!("{} must select exactly one column", context));
+        }
+        Ok(query)
+    }
+
+    fn continues_predicate(&self) -> bool {
+        matches!(self.peek(), Token::Operator(_))
+            || self.check_keyword("IS")
+            || self.check_keyword("IN")
+            || self.check_keyword("LIKE")
+            || self.check_keyword("NOT")
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        self.parse_binary_expression(0)
+    }
+
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, String> {
+        let left = self.parse_postfix_expression()?;
+        self.continue_binary_expression(left, min_precedence)
+    }
+
+    fn continue_binary_expression(&mut self, mut left: Expression, min_precedence: u8) -> Result<Expression, String> {
+        while let Some(operator) = BinaryOperator::from_token(self.peek()) {
+            if operator.precedence() < min_precedence {
                 break;
             }
+            self.advance();
+            let right = self.parse_binary_expression(operator.precedence() + 1)?;
+            left = Expression::Binary(BinaryExpression {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            });
         }
-        Ok(changes)
+
+        Ok(left)
     }
 
-    fn parse_set(&mut self) -> Result<UpdateSet, String>{
-        let column = self.parse_column()?;
+    fn parse_postfix_expression(&mut self) -> Result<Expression, String> {
+        let expression = self.parse_primary_expression()?;
+        self.continue_postfix_expression(expression)
+    }
 
-        if let Token::Operator(op) = self.advance() {
-            if op != "=" {
-                return Err("Expected '=' in SET clause".to_string());
-            }
-        } else {
-            return Err("Expected '=' operator in SET clause".to_string());
+    fn continue_postfix_expression(&mut self, mut expression: Expression) -> Result<Expression, String> {
+        while self.check_keyword("AT") {
+            self.advance();
+            self.consume_word("TIME")?;
+            self.consume_word("ZONE")?;
+            let zone = match self.advance() {
+                Token::StringLiteral(zone) => TimeZone::parse(&zone)?,
+                _ => return Err("Expected time zone string after AT TIME ZONE".to_string()),
+            };
+            expression = Expression::AtTimeZone(AtTimeZoneExpression {
+                expression: Box::new(expression),
+                zone,
+            });
         }
 
-        let value = self.parse_value()?;
+        Ok(expression)
+    }
 
-        Ok(UpdateSet{
-            column: column,
-            value: value,
-        })      
+    fn parse_primary_expression(&mut self) -> Result<Expression, String> {
+        if self.is_subquery_start() {
+            return Ok(Expression::Subquery(Box::new(self.parse_single_column_subquery("Scalar subquery")?)));
+        }
+        match self.advance() {
+            Token::Delimiter('(') => {
+                let expression = self.parse_expression()?;
+                self.consume_token(Token::Delimiter(')'))?;
+                Ok(expression)
+            }
+            Token::Operator(ref op) if op == "-" => match self.parse_postfix_expression()? {
+                Expression::Value(value) => Ok(Expression::Value(negate_value(value)?)),
+                operand => Ok(Expression::Negate(Box::new(operand))),
+            },
+            Token::Identifier(ref name) if self.is_typed_literal(name) => {
+                Ok(Expression::Value(self.parse_typed_literal(name)?))
+            }
+            Token::Identifier(ref name) if name.eq_ignore_ascii_case("CAST") && self.peek() == &Token::Delimiter('(') => {
+                self.parse_cast()
+            }
+            Token::Identifier(name) if self.peek() == &Token::Delimiter('(') => {
+                if let Some(function) = RankingFunction::lookup(&name) {
+                    self.consume_token(Token::Delimiter('('))?;
+                    if self.peek() != &Token::Delimiter(')') {
+                        return Err(format!("{} takes no arguments", function.name()));
+                    }
+                    self.advance();
+                    if !self.check_word("OVER") {
+                        return Err(format!("{} requires an OVER clause", function.name()));
+                    }
+                    return self.parse_window_call(WindowFunction::Ranking(function));
+                }
+                match AggregateFunction::lookup(&name) {
+                    Some(function) => {
+                        let aggregate = self.parse_aggregate_call(function)?;
+                        if !self.check_word("OVER") {
+                            return Ok(Expression::Aggregate(aggregate));
+                        }
+                        if aggregate.distinct {
+                            return Err("DISTINCT is not supported in window aggregates".to_string());
+                        }
+                        self.parse_window_call(WindowFunction::Aggregate(aggregate))
+                    }
+                    None => Ok(Expression::Function(self.parse_function_call(name)?)),
+                }
+            }
+            Token::Identifier(name) => Ok(Expression::Field(self.parse_column_reference(name)?)),
+            Token::StringLiteral(text) => Ok(Expression::Value(Value::Text(text))),
+            Token::Float(float) => Ok(Expression::Value(Value::Float(float))),
+            Token::Number(integer) => Ok(Expression::Value(Value::Integer(integer))),
+            Token::Boolean(boolean) => Ok(Expression::Value(Value::Boolean(boolean))),
+            Token::BlobLiteral(bytes) => Ok(Expression::Value(Value::Blob(bytes))),
+            Token::Keyword(ref keyword) if keyword == "NULL" => Ok(Expression::Value(Value::Null)),
+            Token::Keyword(ref keyword) if keyword == "CASE" => Ok(Expression::Case(self.parse_case()?)),
+            _ => Err("Expected expression".to_string()),
+        }
     }
 
-    fn parse_value_list(&mut self) -> Result<Vec<Value>, String> {
-        let mut values = Vec::new();
+    fn parse_function_call(&mut self, name: String) -> Result<FunctionCall, String> {
+        let function = match functions::lookup(&name) {
+            Some(function) => function,
+            None => return Err(format!("Unknown function: {}", name)),
+        };
 
-        loop {
-            values.push(self.parse_value()?);
-            if self.peek() == &Token::Delimiter(',') {
-                self.advance(); 
-            } else {
-                break;
+        self.consume_token(Token::Delimiter('('))?;
+        let mut arguments = Vec::new();
+        if self.peek() != &Token::Delimiter(')') {
+            loop {
+                arguments.push(self.parse_function_argument()?);
+                if self.peek() == &Token::Delimiter(',') {
+                    self.advance();
+                } else {
+                    break;
+                }
             }
         }
+        self.consume_token(Token::Delimiter(')'))?;
 
-        Ok(values)
+        function.check_arity(arguments.len())?;
+        Ok(FunctionCall {
+            name: function.name.to_string(),
+            arguments,
+        })
     }
 
-    fn parse_value(&mut self) -> Result<Value, String>{
-        let token = self.advance();
-        match token{
-            Token::Float(value) => return Ok(Value::Float(value)),
-            Token::Number(value) => return Ok(Value::Integer(value)),
-            Token::StringLiteral(text) =>  return Ok(Value::Text(text)),
-            _ => return Err("Expected value".to_string()),
+    fn parse_function_argument(&mut self) -> Result<Expression, String> {
+        match self.parse_condition()? {
+            Condition::Expression(expression) => Ok(expression),
+            condition => Ok(Expression::Predicate(Box::new(condition))),
         }
     }
 
-    fn parse_column_list(&mut self) -> Result<Vec<Column>, String> {
-        let mut columns = Vec::new();
-
-        loop {
-            columns.push(self.parse_column()?);
-            if self.peek() == &Token::Delimiter(',') {
-                self.advance(); 
-            } else {
-                break;
+    fn parse_aggregate_call(&mut self, function: AggregateFunction) -> Result<AggregateCall, String> {
+        self.consume_token(Token::Delimiter('('))?;
+        let distinct = self.check_keyword("DISTINCT");
+        if distinct {
+            self.advance();
+        }
+        let argument = if self.peek() == &Token::Operator("*".to_string()) {
+            if distinct {
+                return Err("DISTINCT cannot be used with *".to_string());
+            }
+            if function != AggregateFunction::Count {
+                return Err(format!("{}(*) is not supported, only COUNT(*)", function.name()));
+            }
+            self.advance();
+            AggregateArgument::Star
+        } else {
+            let expression = self.with_windows(false, Self::parse_expression)?;
+            if expression.contains_aggregate() {
+                return Err("Aggregate function calls cannot be nested".to_string());
             }
+            AggregateArgument::Expression(Box::new(expression))
+        };
+
+        if self.peek() != &Token::Delimiter(')') {
+            return Err(format!("{} expects exactly one argument", function.name()));
         }
+        self.advance();
 
-        Ok(columns)
+        Ok(AggregateCall {
+            function,
+            distinct,
+            argument,
+        })
     }
 
-    fn parse_column(&mut self) -> Result<Column, String> {
-        if let Token::Identifier(name) = self.advance() {
-            Ok(Column { name })
-        } else {
-            Err("Expected column name".to_string())
+    fn parse_cast(&mut self) -> Result<Expression, String> {
+        self.consume_token(Token::Delimiter('('))?;
+        let expression = self.parse_expression()?;
+        self.consume_token(Token::Keyword("AS".to_string()))?;
+        let data_type = self.parse_data_type()?;
+        self.consume_token(Token::Delimiter(')'))?;
+
+        // Literals are converted right away so invalid casts are reported before execution.
+        match expression {
+            Expression::Value(value) => Ok(Expression::Value(coercion::cast(value, &data_type)?)),
+            expression => Ok(Expression::Cast(CastExpression {
+                expression: Box::new(expression),
+                data_type,
+            })),
         }
     }
 
-    fn parse_table(&mut self) -> Result<Table, String> {
-        if let Token::Identifier(name) = self.advance() {
-            Ok(Table { name })
+    fn parse_case(&mut self) -> Result<CaseExpression, String> {
+        let operand = if self.check_keyword("WHEN") {
+            None
         } else {
-            Err("Expected table name".to_string())
+            Some(Box::new(self.parse_expression()?))
+        };
+
+        let mut branches = Vec::new();
+        while self.check_keyword("WHEN") {
+            self.advance();
+            let condition = match operand {
+                Some(_) => Condition::Expression(self.parse_expression()?),
+                None => self.parse_condition()?,
+            };
+            self.consume_token(Token::Keyword("THEN".to_string()))?;
+            let result = self.parse_function_argument()?;
+            branches.push(CaseBranch { condition, result });
+        }
+        if branches.is_empty() {
+            return Err("CASE requires at least one WHEN branch".to_string());
         }
-    }
 
-    fn parse_condition(&mut self) -> Result<Condition, String> {
-        let left = self.parse_expression()?;
-        let operator = self.parse_operator()?;
-        let right = self.parse_expression()?;
+        let else_result = if self.check_keyword("ELSE") {
+            self.advance();
+            self.parse_function_argument()?
+        } else {
+            Expression::Value(Value::Null)
+        };
+        self.consume_token(Token::Keyword("END".to_string()))?;
 
-        Ok(Condition {
-            left,
-            operator,
-            right,
+        Ok(CaseExpression {
+            operand,
+            branches,
+            else_result: Box::new(else_result),
         })
     }
 
-    fn parse_expression(&mut self) -> Result<ConditionEnum, String> {
-        match self.advance() {
-            Token::Identifier(name) => Ok(ConditionEnum::Field(Column { name })),
-            Token::StringLiteral(text) => Ok(ConditionEnum::Value(Value::Text(text))),
-            Token::Float(float) => Ok(ConditionEnum::Value(Value::Float(float))),
-            Token::Number(integer) => Ok(ConditionEnum::Value(Value::Integer(integer))),
-            _ => Err("Expected expression".to_string()),
+    fn parse_window_call(&mut self, function: WindowFunction) -> Result<Expression, String> {
+        if !self.windows_allowed {
+            return Err("Window functions are only allowed in the select list and ORDER BY".to_string());
         }
+        self.consume_word("OVER")?;
+        self.consume_token(Token::Delimiter('('))?;
+        let over = self.with_windows(false, Self::parse_window_specification)?;
+        self.consume_token(Token::Delimiter(')'))?;
+        Ok(Expression::Window(WindowCall { function, over }))
+    }
+
+    fn parse_window_specification(&mut self) -> Result<WindowSpecification, String> {
+        let mut partition_by = Vec::new();
+        if self.check_word("PARTITION") {
+            self.advance();
+            self.consume_token(Token::Keyword("BY".to_string()))?;
+            loop {
+                partition_by.push(self.parse_expression()?);
+                if self.peek() == &Token::Delimiter(',') {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+        let order_by = self.parse_order_by()?;
+        Ok(WindowSpecification { partition_by, order_by })
+    }
+
+    fn with_windows<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let enclosing = std::mem::replace(&mut self.windows_allowed, allowed);
+        let result = parse(self);
+        self.windows_allowed = enclosing;
+        result
     }
 
     fn parse_operator(&mut self) -> Result<Operator, String> {
@@ -351,7 +3125,371 @@ impl <'a>Parser<'a>{
         }
     }
 
+    // Matches a non-reserved word such as TIME, which may also name a column.
+    fn consume_word(&mut self, word: &str) -> Result<(), String> {
+        match self.advance() {
+            Token::Identifier(ref name) if name.eq_ignore_ascii_case(word) => Ok(()),
+            token => Err(format!("Expected {}, found {:?}", word, token)),
+        }
+    }
+
+    fn check_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Token::Identifier(name) if name.eq_ignore_ascii_case(word))
+    }
+
     fn check(&self, expected: &Token) -> bool {
         self.peek() == expected
     }
 }
+
+fn negate_value(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Integer(integer) => integer
+            .checked_neg()
+            .map(Value::Integer)
+            .ok_or_else(|| format!("Integer out of range: -({})", integer)),
+        Value::Float(float) => Ok(Value::Float(-float)),
+        Value::Decimal(decimal) => match decimal.units().checked_neg() {
+            Some(units) => Ok(Value::Decimal(Decimal::new(units, decimal.scale())?)),
+            None => Err(format!("Decimal out of range: -({:?})", decimal)),
+        },
+        other => Err(format!("Cannot negate {:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+    use std::time::{Duration, Instant};
+
+    fn parse(sql: &str) -> Result<Query, String> {
+        let mut tokenizer = Tokenizer::new(sql);
+        let tokens = tokenizer.tokenize()?;
+        Parser::new(tokens).parse()
+    }
+
+    fn where_clause(sql: &str) -> Condition {
+        match parse(sql) {
+            Ok(Query::Select(query)) => query.where_clause.expect("no WHERE clause"),
+            other => panic!("expected a SELECT, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nested_function_arguments_parse_in_linear_time() {
+        let mut condition = "x + 0 > 0".to_string();
+        for _ in 0..20 {
+            condition = format!("(IIF({}, 1, 0)) > 0", condition);
+        }
+        let sql = format!("SELECT a FROM t WHERE {};", condition);
+
+        let started = Instant::now();
+        let mut condition = &where_clause(&sql);
+        assert!(started.elapsed() < Duration::from_secs(1), "took {:?}", started.elapsed());
+
+        let mut depth = 0;
+        while let Condition::Comparison(Comparison { left: Expression::Function(function), .. }) = condition {
+            assert_eq!(function.name, "IIF");
+            condition = match &function.arguments[0] {
+                Expression::Predicate(predicate) => predicate,
+                other => panic!("expected a predicate argument, got {:?}", other),
+            };
+            depth += 1;
+        }
+        assert_eq!(depth, 20);
+        assert!(matches!(condition, Condition::Comparison(_)));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let condition = where_clause("SELECT a FROM t WHERE a = 1 OR b = 2 AND c = 3;");
+        match condition {
+            Condition::Or(left, right) => {
+                assert!(matches!(*left, Condition::Comparison(_)));
+                assert!(matches!(*right, Condition::And(_, _)));
+            }
+            other => panic!("expected OR at the top, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        let condition = where_clause("SELECT a FROM t WHERE (a = 1 OR b = 2) AND NOT c = 3;");
+        match condition {
+            Condition::And(left, right) => {
+                assert!(matches!(*left, Condition::Or(_, _)));
+                assert!(matches!(*right, Condition::Not(_)));
+            }
+            other => panic!("expected AND at the top, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        let condition = where_clause("SELECT a FROM t WHERE a + b * 2 > (a + 1) * 2;");
+        let Condition::Comparison(comparison) = condition else {
+            panic!("expected a comparison, got {:?}", condition);
+        };
+        match &comparison.left {
+            Expression::Binary(BinaryExpression { operator: BinaryOperator::Add, right, .. }) => {
+                assert!(matches!(**right, Expression::Binary(BinaryExpression { operator: BinaryOperator::Multiply, .. })));
+            }
+            other => panic!("expected an addition, got {:?}", other),
+        }
+        match &comparison.right {
+            Expression::Binary(BinaryExpression { operator: BinaryOperator::Multiply, left, .. }) => {
+                assert!(matches!(**left, Expression::Binary(BinaryExpression { operator: BinaryOperator::Add, .. })));
+            }
+            other => panic!("expected a multiplication, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parenthesized_expression_continues_as_predicate() {
+        assert!(matches!(where_clause("SELECT a FROM t WHERE (a) IS NULL;"), Condition::IsNull(_)));
+        assert!(matches!(where_clause("SELECT a FROM t WHERE (a + 1) IN (1, 2);"), Condition::In(_)));
+        assert!(matches!(where_clause("SELECT a FROM t WHERE ((a + 1)) > 2;"), Condition::Comparison(_)));
+    }
+
+    #[test]
+    fn deeply_nested_groups_parse_in_linear_time() {
+        let mut condition = "a > 0".to_string();
+        for depth in 0..20 {
+            condition = format!("(({}) OR b = {})", condition, depth);
+        }
+        let sql = format!("SELECT a FROM t WHERE {};", condition);
+
+        let started = Instant::now();
+        let mut condition = &where_clause(&sql);
+        assert!(started.elapsed() < Duration::from_secs(1), "took {:?}", started.elapsed());
+
+        let mut depth = 0;
+        while let Condition::Or(left, _) = condition {
+            condition = left;
+            depth += 1;
+        }
+        assert_eq!(depth, 20);
+        assert!(matches!(condition, Condition::Comparison(_)));
+    }
+
+    #[test]
+    fn very_deep_nesting_parses() {
+        let depth = 200;
+        let sql = format!("SELECT a FROM t WHERE {}a > 1{};", "(".repeat(depth), ")".repeat(depth));
+        assert!(matches!(where_clause(&sql), Condition::Comparison(_)));
+    }
+
+    #[test]
+    fn negating_the_smallest_integer_is_an_error() {
+        let error = parse("SELECT -CAST('-9223372036854775808' AS INTEGER) FROM t;").unwrap_err();
+        assert!(error.contains("out of range"), "{}", error);
+        assert!(parse("SELECT -CAST('-9223372036854775807' AS INTEGER) FROM t;").is_ok());
+    }
+
+    #[test]
+    fn cursor_words_are_not_reserved() {
+        assert!(parse("SELECT open, close, fetch, cursor, declare FROM prices;").is_ok());
+        assert!(matches!(parse("DECLARE c CURSOR FOR SELECT a FROM t;"), Ok(Query::DeclareCursor(_))));
+        assert!(matches!(parse("fetch 10 FROM c;"), Ok(Query::Fetch(_))));
+        assert!(matches!(parse("CLOSE c;"), Ok(Query::CloseCursor(_))));
+    }
+
+    #[test]
+    fn savepoint_words_are_not_reserved() {
+        assert!(parse("SELECT release, savepoint, rollback FROM versions;").is_ok());
+        assert!(matches!(parse("SAVEPOINT before_import;"), Ok(Query::Savepoint(_))));
+        assert!(matches!(parse("ROLLBACK TO SAVEPOINT before_import;"), Ok(Query::RollbackTo(_))));
+        assert!(matches!(parse("release before_import;"), Ok(Query::Release(_))));
+    }
+
+    #[test]
+    fn snapshot_words_are_not_reserved() {
+        assert!(parse("SELECT snapshot, attach FROM backups;").is_ok());
+        assert!(matches!(parse("SNAPSHOT CREATE before_migration;"), Ok(Query::CreateSnapshot(_))));
+        assert!(matches!(parse("attach snapshot before_migration AS old;"), Ok(Query::AttachSnapshot(_))));
+        assert!(matches!(parse("ATTACH 'other.db' AS other;"), Ok(Query::Attach(_))));
+    }
+
+    #[test]
+    fn pragma_is_not_reserved() {
+        assert!(parse("SELECT pragma FROM settings;").is_ok());
+        assert!(matches!(parse("pragma table_info('t');"), Ok(Query::Select(_))));
+    }
+
+    #[test]
+    fn channel_words_are_not_reserved() {
+        assert!(parse("SELECT notify, listen FROM subscriptions;").is_ok());
+        assert!(matches!(parse("NOTIFY jobs, 'done';"), Ok(Query::Notify(_))));
+        assert!(matches!(parse("listen jobs;"), Ok(Query::Listen(_))));
+        assert!(matches!(parse("UNLISTEN jobs;"), Ok(Query::Unlisten(_))));
+    }
+
+    #[test]
+    fn grouped_expressions_match_group_by_keys() {
+        assert!(parse("SELECT a + 1 FROM t GROUP BY a + 1;").is_ok());
+        assert!(parse("SELECT UPPER(name), COUNT(*) FROM t GROUP BY upper(t.name);").is_ok());
+        assert!(parse("SELECT (a + 1) * 2, SUM(b) FROM t GROUP BY a + 1 HAVING a + 1 > 0 ORDER BY a + 1;").is_ok());
+        assert!(parse("SELECT a, a + 1 FROM t GROUP BY a;").is_ok());
+    }
+
+    #[test]
+    fn ungrouped_columns_are_rejected() {
+        assert!(parse("SELECT a + 2 FROM t GROUP BY a + 1;").is_err());
+        assert!(parse("SELECT a FROM t GROUP BY a + 1;").is_err());
+        assert!(parse("SELECT UPPER(name), COUNT(*) FROM t GROUP BY LOWER(name);").is_err());
+    }
+
+    #[test]
+    fn timestamptz_compares_with_local_timestamps() {
+        assert!(parse("SELECT a FROM t WHERE TIMESTAMPTZ '2024-01-01 00:00:00Z' > TIMESTAMP '2024-01-01 00:00:00';").is_ok());
+        assert!(parse("SELECT a FROM t WHERE DATE '2024-01-01' <= TIMESTAMPTZ '2024-01-01 00:00:00+02:00';").is_ok());
+        assert!(parse("SELECT a FROM t WHERE TIMESTAMPTZ '2024-01-01 00:00:00Z' = 'x';").is_err());
+    }
+
+    #[test]
+    fn every_column_constraint_can_be_named() {
+        let sql = "CREATE TABLE t (a INT CONSTRAINT nn NOT NULL CONSTRAINT d DEFAULT 1, \
+                   b TEXT CONSTRAINT n NULL CONSTRAINT u UNIQUE CONSTRAINT c CHECK (b != ''), \
+                   c INT CONSTRAINT f REFERENCES parent);";
+        assert!(matches!(parse(sql), Ok(Query::CreateTable(_))));
+        assert!(parse("CREATE TABLE t (a TEXT CONSTRAINT z COMPRESSED);").is_err());
+    }
+
+    fn outer_join(select_list: &str, condition: &str) -> SelectQuery {
+        let sql = format!(
+            "SELECT {} FROM customers c LEFT JOIN orders o ON o.customer = c.id WHERE {};",
+            select_list, condition
+        );
+        match parse(&sql) {
+            Ok(Query::Select(query)) => query,
+            other => panic!("{}: expected a SELECT, got {:?}", sql, other),
+        }
+    }
+
+    fn projection(query: &SelectQuery) -> &Expression {
+        match &query.selected_columns[0] {
+            SelectItem::Expression(expression, _) => expression,
+            SelectItem::Wildcard => panic!("expected an expression"),
+        }
+    }
+
+    #[test]
+    fn outer_joins_pad_with_null_and_filter_on_it() {
+        for (join, kind) in [("LEFT", "Left"), ("RIGHT OUTER", "Right"), ("FULL", "Full")] {
+            let sql = format!("SELECT c.id FROM customers c {} JOIN orders o ON o.customer = c.id WHERE o.id IS NULL;", join);
+            let Ok(Query::Select(query)) = parse(&sql) else {
+                panic!("{} did not parse", sql);
+            };
+            assert_eq!(format!("{:?}", query.joins[0].kind), kind);
+            match query.where_clause {
+                Some(Condition::IsNull(IsNullCondition { expression: Expression::Field(column), negated: false })) => {
+                    assert_eq!(column.table.as_deref(), Some("o"));
+                }
+                other => panic!("expected o.id IS NULL, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn null_operands_are_accepted_by_every_operator() {
+        for operator in ["+", "-", "*", "/", "%", "||", "&", "|", "<<", ">>"] {
+            let query = outer_join(&format!("o.total {} NULL", operator), &format!("NULL {} o.total IS NULL", operator));
+            assert!(matches!(projection(&query), Expression::Binary(_)), "{}", operator);
+        }
+        for operator in ["=", "!=", "<", "<=", ">", ">="] {
+            let query = outer_join("o.total", &format!("o.placed {} NULL OR NULL {} DATE '2024-01-01'", operator, operator));
+            assert!(matches!(query.where_clause, Some(Condition::Or(_, _))), "{}", operator);
+        }
+        outer_join("o.total", "o.total IN (1, NULL) AND o.note LIKE 'a%' AND NOT o.total NOT IN (NULL)");
+    }
+
+    #[test]
+    fn null_operands_are_accepted_by_every_function_family() {
+        let calls = [
+            "COALESCE(o.total, NULL, 0)",
+            "IFNULL(NULL, c.id)",
+            "NULLIF(o.total, NULL)",
+            "UPPER(NULL)",
+            "SUBSTR(o.note, NULL)",
+            "ABS(NULL)",
+            "ROUND(o.total, NULL)",
+            "DATE_PART('year', NULL)",
+            "IIF(o.id IS NULL, NULL, o.total)",
+            "GREATEST(NULL, o.total)",
+            "TYPEOF(NULL)",
+            "MD5(NULL)",
+        ];
+        for call in calls {
+            let query = outer_join(call, &format!("{} IS NULL", call));
+            assert!(matches!(projection(&query), Expression::Function(_)), "{}", call);
+        }
+        let query = outer_join("COUNT(o.id), SUM(o.total)", "c.id > 0 GROUP BY c.id");
+        assert!(matches!(projection(&query), Expression::Aggregate(_)));
+    }
+
+    #[test]
+    fn constant_folding_propagates_null() {
+        let query = outer_join("-NULL", "CAST(NULL AS INTEGER) IS NULL");
+        assert!(matches!(projection(&query), Expression::Value(Value::Null)));
+        for data_type in ["INTEGER", "FLOAT", "TEXT", "BOOLEAN", "DATE", "TIMESTAMP", "TIMESTAMPTZ", "DECIMAL(10, 2)", "BLOB"] {
+            let query = outer_join(&format!("CAST(NULL AS {})", data_type), "o.id IS NULL");
+            assert!(matches!(projection(&query), Expression::Value(Value::Null)), "{}", data_type);
+        }
+    }
+
+    #[test]
+    fn equality_with_null_on_the_padded_side_is_flagged() {
+        let query = parse("SELECT c.id FROM customers c LEFT JOIN orders o ON o.customer = c.id WHERE o.id = NULL;").unwrap();
+        assert!(query.compares_with_null());
+        let query = parse("SELECT c.id FROM customers c LEFT JOIN orders o ON o.customer = c.id WHERE o.id IS NULL;").unwrap();
+        assert!(!query.compares_with_null());
+    }
+
+    #[test]
+    fn unbalanced_parentheses_are_rejected() {
+        assert!(parse("SELECT a FROM t WHERE ((a > 1);").is_err());
+        assert!(parse("SELECT a FROM t WHERE (a > 1));").is_err());
+    }
+
+    fn evaluate(sql: &str) -> Result<Vec<Vec<Value>>, String> {
+        match parse(sql)? {
+            Query::Select(query) => query.evaluate_constant(&Session::new()),
+            other => panic!("expected a SELECT, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn select_without_from_returns_one_row() {
+        let rows = evaluate("SELECT 1 + 2 * 3 AS total, 'a' || 'b', CASE 2 WHEN 1 THEN 'one' WHEN 2 THEN 'two' END;").unwrap();
+        assert_eq!(rows, vec![vec![Value::Integer(7), Value::Text("ab".to_string()), Value::Text("two".to_string())]]);
+        assert_eq!(evaluate("SELECT 1 WHERE 1 = 0;").unwrap(), Vec::<Vec<Value>>::new());
+        assert_eq!(evaluate("SELECT 1 LIMIT 0;").unwrap(), Vec::<Vec<Value>>::new());
+
+        let Ok(Query::Select(query)) = parse("SELECT 1 AS one, 2;") else { panic!() };
+        assert_eq!(query.column_names(), vec!["one".to_string(), "column2".to_string()]);
+    }
+
+    #[test]
+    fn constant_conditions_use_three_valued_logic() {
+        let row = |sql: &str| evaluate(sql).unwrap().remove(0).remove(0);
+        assert_eq!(row("SELECT IIF(NULL = 1 OR 1 = 1, 'yes', 'no');"), Value::Text("yes".to_string()));
+        assert_eq!(row("SELECT IIF(NULL = 1 AND 1 = 1, 'yes', 'no');"), Value::Text("no".to_string()));
+        assert_eq!(row("SELECT NULLIF(NULL = 1 OR 1 = 2, TRUE);"), Value::Null);
+        assert_eq!(row("SELECT IIF(3 NOT IN (1, 2), 1, 0);"), Value::Integer(1));
+    }
+
+    #[test]
+    fn coalesce_skips_arguments_it_does_not_return() {
+        assert_eq!(evaluate("SELECT COALESCE(NULL, 2, 1 / 0);").unwrap(), vec![vec![Value::Integer(2)]]);
+        assert!(evaluate("SELECT COALESCE(NULL, 1 / 0);").is_err());
+    }
+
+    #[test]
+    fn select_without_from_rejects_columns_and_wildcards() {
+        assert!(evaluate("SELECT a;").unwrap_err().contains("needs a FROM clause"));
+        assert!(parse("SELECT *;").is_err());
+        assert!(evaluate("SELECT COUNT(*);").is_err());
+    }
+}
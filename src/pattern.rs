@@ -0,0 +1,132 @@
+use std::fmt;
+
+enum PatternElement {
+    Literal(char),
+    AnyChar,
+    AnyString,
+}
+
+pub struct LikePattern {
+    pattern: String,
+    escape: Option<char>,
+    elements: Vec<PatternElement>,
+}
+
+impl LikePattern {
+    pub fn compile(pattern: &str, escape: Option<char>) -> Result<Self, String> {
+        let mut elements = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let element = if Some(c) == escape {
+                match chars.next() {
+                    Some(escaped) => PatternElement::Literal(escaped),
+                    None => return Err(format!("LIKE pattern '{}' ends with the escape character", pattern)),
+                }
+            } else if c == '%' {
+                PatternElement::AnyString
+            } else if c == '_' {
+                PatternElement::AnyChar
+            } else {
+                PatternElement::Literal(c)
+            };
+            elements.push(element);
+        }
+
+        Ok(LikePattern {
+            pattern: pattern.to_string(),
+            escape,
+            elements,
+        })
+    }
+
+    // Greedy match that backtracks to the most recent '%' on a mismatch.
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let mut text_position = 0;
+        let mut pattern_position = 0;
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while text_position < text.len() {
+            match self.elements.get(pattern_position) {
+                Some(PatternElement::Literal(c)) if *c == text[text_position] => {
+                    text_position += 1;
+                    pattern_position += 1;
+                }
+                Some(PatternElement::AnyChar) => {
+                    text_position += 1;
+                    pattern_position += 1;
+                }
+                Some(PatternElement::AnyString) => {
+                    backtrack = Some((pattern_position, text_position));
+                    pattern_position += 1;
+                }
+                _ => match backtrack {
+                    Some((wildcard_position, matched_until)) => {
+                        pattern_position = wildcard_position + 1;
+                        text_position = matched_until + 1;
+                        backtrack = Some((wildcard_position, matched_until + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.elements[pattern_position..]
+            .iter()
+            .all(|element| matches!(element, PatternElement::AnyString))
+    }
+}
+
+impl fmt::Debug for LikePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.escape {
+            Some(escape) => write!(f, "LikePattern({:?} ESCAPE {:?})", self.pattern, escape),
+            None => write!(f, "LikePattern({:?})", self.pattern),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn like(text: &str, pattern: &str) -> bool {
+        LikePattern::compile(pattern, None).unwrap().matches(text)
+    }
+
+    #[test]
+    fn wildcards_backtrack() {
+        assert!(like("abcbcd", "a%bcd"));
+        assert!(like("abcbcd", "%b_d"));
+        assert!(like("aaa", "%a%a%a%"));
+        assert!(like("héllo", "h_llo"));
+        assert!(!like("abcbce", "a%bcd"));
+        assert!(!like("ab", "a__"));
+        assert!(!like("aa", "%a%a%a%"));
+    }
+
+    #[test]
+    fn escaped_wildcards_are_literal() {
+        let pattern = LikePattern::compile("100!%%", Some('!')).unwrap();
+        assert!(pattern.matches("100% sure"));
+        assert!(!pattern.matches("1000"));
+        let pattern = LikePattern::compile("a!_b!!", Some('!')).unwrap();
+        assert!(pattern.matches("a_b!"));
+        assert!(!pattern.matches("axb!"));
+    }
+
+    #[test]
+    fn trailing_escape_character_is_an_error() {
+        assert!(LikePattern::compile("abc!", Some('!')).is_err());
+        assert!(LikePattern::compile("abc!", None).is_ok());
+    }
+
+    #[test]
+    fn empty_pattern_and_input() {
+        assert!(like("", ""));
+        assert!(like("", "%"));
+        assert!(like("", "%%"));
+        assert!(!like("", "_"));
+        assert!(!like("a", ""));
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::tokenizer::Token;
+
+#[derive(Debug, Default)]
+pub struct ProfileEntry {
+    calls: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl ProfileEntry {
+    fn record(&mut self, elapsed: Duration) {
+        self.calls += 1;
+        self.total += elapsed;
+        if elapsed > self.max {
+            self.max = elapsed;
+        }
+    }
+}
+
+// Time spent in each phase of a single statement, in the order the phases ran.
+pub struct StatementTiming {
+    phase_started: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StatementTiming {
+    pub fn start() -> Self {
+        StatementTiming {
+            phase_started: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    // Ends the current phase and starts the next one.
+    pub fn phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.phase_started));
+        self.phase_started = now;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
+
+impl fmt::Display for StatementTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Time: {:.3?} (", self.total())?;
+        for (index, (name, elapsed)) in self.phases.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {:.3?}", name, elapsed)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    enabled: bool,
+    by_statement: HashMap<String, ProfileEntry>,
+    by_kind: HashMap<&'static str, ProfileEntry>,
+    by_phase: HashMap<&'static str, ProfileEntry>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn reset(&mut self) {
+        self.by_statement.clear();
+        self.by_kind.clear();
+        self.by_phase.clear();
+    }
+
+    pub fn record(&mut self, tokens: &[Token], kind: &'static str, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+        self.by_statement
+            .entry(fingerprint(tokens))
+            .or_default()
+            .record(elapsed);
+        self.by_kind.entry(kind).or_default().record(elapsed);
+    }
+
+    // Called once a statement has finished, with all of its phases.
+    pub fn record_phases(&mut self, timing: &StatementTiming) {
+        if !self.enabled {
+            return;
+        }
+        for (name, elapsed) in &timing.phases {
+            self.by_phase.entry(name).or_default().record(*elapsed);
+        }
+    }
+
+    pub fn report(&self) {
+        if self.by_statement.is_empty() {
+            println!("No statements profiled. Use .profile on to start profiling.");
+            return;
+        }
+
+        println!("By statement type:");
+        for (kind, entry) in sorted_by_total(&self.by_kind) {
+            print_entry(kind, entry);
+        }
+
+        println!("By phase:");
+        for (phase, entry) in sorted_by_total(&self.by_phase) {
+            print_entry(phase, entry);
+        }
+
+        println!("By statement:");
+        for (statement, entry) in sorted_by_total(&self.by_statement) {
+            print_entry(statement, entry);
+        }
+    }
+}
+
+// Statements differing only in their literals share a fingerprint,
+// so `WHERE id = 1` and `WHERE id = 2` are aggregated together.
+pub fn fingerprint(tokens: &[Token]) -> String {
+    let mut parts = Vec::new();
+    for token in tokens {
+        let part = match token {
+            Token::Keyword(keyword) => keyword.clone(),
+            Token::Identifier(name) => name.clone(),
+            Token::Float(_) | Token::Number(_) | Token::StringLiteral(_) | Token::Boolean(_)
+            | Token::BlobLiteral(_) => "?".to_string(),
+            Token::Operator(op) => op.clone(),
+            Token::Delimiter(c) => c.to_string(),
+        };
+        parts.push(part);
+    }
+    parts.join(" ")
+}
+
+fn sorted_by_total<K: AsRef<str>>(entries: &HashMap<K, ProfileEntry>) -> Vec<(&str, &ProfileEntry)> {
+    let mut sorted: Vec<(&str, &ProfileEntry)> = entries
+        .iter()
+        .map(|(key, entry)| (key.as_ref(), entry))
+        .collect();
+    sorted.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.total));
+    sorted
+}
+
+fn print_entry(label: &str, entry: &ProfileEntry) {
+    let average = entry.total / entry.calls;
+    println!(
+        "  {:>6} calls  total {:>10.3?}  avg {:>10.3?}  max {:>10.3?}  {}",
+        entry.calls, entry.total, average, entry.max, label
+    );
+}
@@ -0,0 +1,281 @@
+use crate::parser::Value;
+use crate::temporal::{Date, Timestamp};
+use crate::decimal::Decimal;
+
+// Layout: [version][varint column count][null bitmap][tagged values...]
+// Null columns only set their bitmap bit and have no tagged value.
+pub const ROW_FORMAT_VERSION: u8 = 1;
+
+const TAG_INTEGER: u8 = 1;
+const TAG_FLOAT: u8 = 2;
+const TAG_TEXT: u8 = 3;
+const TAG_BOOLEAN: u8 = 4;
+const TAG_DATE: u8 = 5;
+const TAG_TIMESTAMP: u8 = 6;
+const TAG_DECIMAL: u8 = 7;
+const TAG_BLOB: u8 = 8;
+const TAG_TIMESTAMPTZ: u8 = 9;
+
+#[allow(dead_code)]
+pub fn encode_row(values: &[Value]) -> Vec<u8> {
+    let mut bytes = vec![ROW_FORMAT_VERSION];
+    write_varint(&mut bytes, values.len() as u64);
+
+    let mut null_bitmap = vec![0u8; values.len().div_ceil(8)];
+    for (column, value) in values.iter().enumerate() {
+        if let Value::Null = value {
+            null_bitmap[column / 8] |= 1 << (column % 8);
+        }
+    }
+    bytes.extend_from_slice(&null_bitmap);
+
+    for value in values {
+        match value {
+            Value::Null => {}
+            Value::Integer(integer) => {
+                bytes.push(TAG_INTEGER);
+                write_varint(&mut bytes, zigzag_encode(*integer));
+            }
+            Value::Float(float) => {
+                bytes.push(TAG_FLOAT);
+                bytes.extend_from_slice(&float.to_bits().to_le_bytes());
+            }
+            Value::Text(text) => {
+                bytes.push(TAG_TEXT);
+                write_varint(&mut bytes, text.len() as u64);
+                bytes.extend_from_slice(text.as_bytes());
+            }
+            Value::Boolean(boolean) => {
+                bytes.push(TAG_BOOLEAN);
+                bytes.push(*boolean as u8);
+            }
+            Value::Date(date) => {
+                bytes.push(TAG_DATE);
+                write_varint(&mut bytes, zigzag_encode(date.days()));
+            }
+            Value::Timestamp(timestamp) => {
+                bytes.push(TAG_TIMESTAMP);
+                write_varint(&mut bytes, zigzag_encode(timestamp.micros()));
+            }
+            Value::TimestampTz(timestamp) => {
+                bytes.push(TAG_TIMESTAMPTZ);
+                write_varint(&mut bytes, zigzag_encode(timestamp.micros()));
+            }
+            Value::Decimal(decimal) => {
+                bytes.push(TAG_DECIMAL);
+                bytes.push(decimal.scale() as u8);
+                bytes.extend_from_slice(&decimal.units().to_le_bytes());
+            }
+            Value::Blob(blob) => {
+                bytes.push(TAG_BLOB);
+                write_varint(&mut bytes, blob.len() as u64);
+                bytes.extend_from_slice(blob);
+            }
+        }
+    }
+    bytes
+}
+
+#[allow(dead_code)]
+pub fn decode_row(bytes: &[u8]) -> Result<Vec<Value>, String> {
+    let mut reader = RowReader::new(bytes);
+
+    let version = reader.read_byte()?;
+    if version > ROW_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported row format version {} (newest supported is {})",
+            version, ROW_FORMAT_VERSION
+        ));
+    }
+
+    let column_count = reader.read_varint()? as usize;
+    let null_bitmap = reader.read_bytes(column_count.div_ceil(8))?.to_vec();
+
+    let mut values = Vec::with_capacity(column_count);
+    for column in 0..column_count {
+        if null_bitmap[column / 8] & (1 << (column % 8)) != 0 {
+            values.push(Value::Null);
+        } else {
+            values.push(reader.read_value()?);
+        }
+    }
+
+    if !reader.is_at_end() {
+        return Err("Trailing bytes after row".to_string());
+    }
+    Ok(values)
+}
+
+struct RowReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> RowReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        RowReader { bytes, position: 0 }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or("Unexpected end of row data")?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        // The length comes from the data itself, so a corrupt one must not overflow.
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Unexpected end of row data")?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            if shift >= 64 {
+                return Err("Varint is too long".to_string());
+            }
+            let byte = self.read_byte()?;
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Value, String> {
+        match self.read_byte()? {
+            TAG_INTEGER => Ok(Value::Integer(zigzag_decode(self.read_varint()?))),
+            TAG_FLOAT => {
+                let mut raw = [0u8; 8];
+                raw.copy_from_slice(self.read_bytes(8)?);
+                Ok(Value::Float(f64::from_bits(u64::from_le_bytes(raw))))
+            }
+            TAG_TEXT => {
+                let length = self.read_varint()? as usize;
+                let raw = self.read_bytes(length)?;
+                match String::from_utf8(raw.to_vec()) {
+                    Ok(text) => Ok(Value::Text(text)),
+                    Err(_) => Err("Invalid UTF-8 in text value".to_string()),
+                }
+            }
+            TAG_BOOLEAN => match self.read_byte()? {
+                0 => Ok(Value::Boolean(false)),
+                1 => Ok(Value::Boolean(true)),
+                byte => Err(format!("Invalid boolean byte {}", byte)),
+            },
+            TAG_DATE => Ok(Value::Date(Date::from_days(zigzag_decode(self.read_varint()?)))),
+            TAG_TIMESTAMP => Ok(Value::Timestamp(Timestamp::from_micros(zigzag_decode(self.read_varint()?)))),
+            TAG_TIMESTAMPTZ => Ok(Value::TimestampTz(Timestamp::from_micros(zigzag_decode(self.read_varint()?)))),
+            TAG_DECIMAL => {
+                let scale = self.read_byte()? as u32;
+                let mut raw = [0u8; 16];
+                raw.copy_from_slice(self.read_bytes(16)?);
+                Ok(Value::Decimal(Decimal::new(i128::from_le_bytes(raw), scale)?))
+            }
+            TAG_BLOB => {
+                let length = self.read_varint()? as usize;
+                Ok(Value::Blob(self.read_bytes(length)?.to_vec()))
+            }
+            tag => Err(format!("Unknown value type tag {}", tag)),
+        }
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_row() -> Vec<Value> {
+        vec![
+            Value::Integer(-42),
+            Value::Null,
+            Value::Float(2.5),
+            Value::Text("zażółć".to_string()),
+            Value::Boolean(true),
+            Value::Date(Date::from_days(-1)),
+            Value::Timestamp(Timestamp::from_micros(1_700_000_000_000_000)),
+            Value::TimestampTz(Timestamp::from_micros(-5)),
+            Value::Decimal(Decimal::new(-12345, 2).unwrap()),
+            Value::Blob(vec![0, 255, 7]),
+            Value::Integer(i64::MIN),
+            Value::Integer(i64::MAX),
+        ]
+    }
+
+    #[test]
+    fn every_value_type_round_trips() {
+        let row = sample_row();
+        assert_eq!(decode_row(&encode_row(&row)).unwrap(), row);
+    }
+
+    #[test]
+    fn null_bitmap_spans_several_bytes() {
+        let row: Vec<Value> = (0..19)
+            .map(|column| if column % 3 == 0 { Value::Null } else { Value::Integer(column) })
+            .collect();
+        let bytes = encode_row(&row);
+        // Version, column count, then three bitmap bytes for 19 columns.
+        assert_eq!(&bytes[..5], &[ROW_FORMAT_VERSION, 19, 0b0100_1001, 0b1001_0010, 0b0000_0100]);
+        assert_eq!(decode_row(&bytes).unwrap(), row);
+
+        let empty: Vec<Value> = Vec::new();
+        assert_eq!(decode_row(&encode_row(&empty)).unwrap(), empty);
+        assert_eq!(decode_row(&encode_row(&[Value::Null])).unwrap(), vec![Value::Null]);
+    }
+
+    #[test]
+    fn version_header_is_checked() {
+        let mut bytes = encode_row(&sample_row());
+        assert_eq!(bytes[0], ROW_FORMAT_VERSION);
+        bytes[0] = ROW_FORMAT_VERSION + 1;
+        assert!(decode_row(&bytes).unwrap_err().contains("Unsupported row format version"));
+        assert!(decode_row(&[]).is_err());
+    }
+
+    #[test]
+    fn corrupt_lengths_are_decode_errors() {
+        // One text column whose length varint is u64::MAX.
+        let mut bytes = vec![ROW_FORMAT_VERSION, 1, 0, TAG_TEXT];
+        write_varint(&mut bytes, u64::MAX);
+        assert!(decode_row(&bytes).is_err());
+
+        let mut truncated = encode_row(&sample_row());
+        truncated.pop();
+        assert!(decode_row(&truncated).is_err());
+
+        let mut trailing = encode_row(&sample_row());
+        trailing.push(0);
+        assert!(decode_row(&trailing).is_err());
+    }
+}
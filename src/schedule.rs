@@ -0,0 +1,82 @@
+use std::fmt;
+
+// Minute, hour, day of month, month and day of week, as in crontab.
+const FIELDS: [(&str, u32, u32); 5] = [
+    ("minute", 0, 59),
+    ("hour", 0, 23),
+    ("day of month", 1, 31),
+    ("month", 1, 12),
+    ("day of week", 0, 6),
+];
+
+// A five-field cron expression. Each field is a bitmask of the values it allows.
+pub struct Schedule {
+    expression: String,
+    #[allow(dead_code)]
+    fields: [u64; 5],
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = expression.split_whitespace().collect();
+        if parts.len() != FIELDS.len() {
+            return Err(format!(
+                "Invalid schedule '{}', expected 5 fields: minute hour day-of-month month day-of-week",
+                expression
+            ));
+        }
+
+        let mut fields = [0; 5];
+        for (index, part) in parts.iter().enumerate() {
+            fields[index] = parse_field(part, FIELDS[index])?;
+        }
+        Ok(Schedule {
+            expression: parts.join(" "),
+            fields,
+        })
+    }
+}
+
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Schedule({:?})", self.expression)
+    }
+}
+
+// Parses a comma-separated list of '*', 'n' or 'a-b', each optionally followed by '/step'.
+fn parse_field(text: &str, (name, min, max): (&str, u32, u32)) -> Result<u64, String> {
+    let mut mask = 0;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, parse_number(step, name)?),
+            None => (item, 1),
+        };
+        if step == 0 {
+            return Err(format!("Schedule {} step cannot be 0", name));
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_number(start, name)?, parse_number(end, name)?)
+        } else {
+            let value = parse_number(range, name)?;
+            (value, value)
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("Schedule {} '{}' is out of range {}-{}", name, item, min, max));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_number(text: &str, name: &str) -> Result<u32, String> {
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid schedule {} '{}'", name, text));
+    }
+    text.parse().map_err(|_| format!("Invalid schedule {} '{}'", name, text))
+}
@@ -0,0 +1,82 @@
+use crate::variables::Variables;
+
+// Tracks .if/.else/.endif blocks; input inside a branch that is not taken is skipped.
+pub struct ScriptBlocks {
+    stack: Vec<Block>,
+}
+
+struct Block {
+    enclosing_active: bool,
+    condition: bool,
+    in_else: bool,
+}
+
+impl ScriptBlocks {
+    pub fn new() -> Self {
+        ScriptBlocks { stack: Vec::new() }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stack
+            .last()
+            .is_none_or(|block| block.enclosing_active && block.condition != block.in_else)
+    }
+
+    // Returns None if the line is not a block directive.
+    pub fn handle(&mut self, line: &str, variables: &Variables) -> Option<Result<(), String>> {
+        let (directive, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match directive {
+            ".if" => {
+                // Conditions in skipped code are not evaluated, so they may use undefined variables.
+                let active = self.is_active();
+                let result = if active { evaluate(argument, variables) } else { Ok(false) };
+                // A condition that cannot be evaluated skips both branches of its block.
+                let enclosing_active = active && result.is_ok();
+                let condition = *result.as_ref().unwrap_or(&false);
+                self.stack.push(Block { enclosing_active, condition, in_else: false });
+                Some(result.map(|_| ()))
+            }
+            ".else" => Some(match self.stack.last_mut() {
+                Some(block) if !block.in_else => {
+                    block.in_else = true;
+                    Ok(())
+                }
+                Some(_) => Err(".else appears twice in the same .if block".to_string()),
+                None => Err(".else without .if".to_string()),
+            }),
+            ".endif" => Some(match self.stack.pop() {
+                Some(_) => Ok(()),
+                None => Err(".endif without .if".to_string()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// Supports 'a == b', 'a != b' and a single value, which is true unless it is
+// empty, 0, false or NULL. Values are compared as text after interpolation.
+fn evaluate(condition: &str, variables: &Variables) -> Result<bool, String> {
+    let condition = variables.interpolate(condition)?;
+    if condition.trim().is_empty() {
+        return Err("Usage: .if value | .if a == b | .if a != b".to_string());
+    }
+
+    if let Some((left, right)) = condition.split_once("==") {
+        return Ok(unquote(left) == unquote(right));
+    }
+    if let Some((left, right)) = condition.split_once("!=") {
+        return Ok(unquote(left) != unquote(right));
+    }
+    let value = unquote(&condition);
+    Ok(!(value.is_empty()
+        || value == "0"
+        || value.eq_ignore_ascii_case("false")
+        || value.eq_ignore_ascii_case("null")))
+}
+
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    text.strip_prefix('\'')
+        .and_then(|inner| inner.strip_suffix('\''))
+        .unwrap_or(text)
+}
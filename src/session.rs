@@ -0,0 +1,159 @@
+use std::mem::discriminant;
+
+use crate::output::markdown_table;
+use crate::parser::Value;
+use crate::temporal::TimeZone;
+
+const OUTPUT_FORMATS: [&str; 3] = ["debug", "pretty", "markdown"];
+const OUTPUT_TARGETS: [&str; 2] = ["stdout", "clipboard"];
+const MATH_DOMAIN_ERRORS: [&str; 2] = ["error", "null"];
+const SANDBOX_LOCKED: [&str; 2] = ["sandbox", "output_target"];
+
+// Every setting's type is the type of its default value.
+pub struct Session {
+    settings: Vec<(&'static str, Value)>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            settings: vec![
+                ("output_format", Value::Text("debug".to_string())),
+                ("output_target", Value::Text("stdout".to_string())),
+                ("safe_updates", Value::Boolean(false)),
+                ("warn_null_comparison", Value::Boolean(true)),
+                ("timezone", Value::Text("UTC".to_string())),
+                ("math_domain_errors", Value::Text("error".to_string())),
+                ("sandbox", Value::Boolean(false)),
+            ],
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let name = name.to_lowercase();
+        self.settings
+            .iter()
+            .find(|(setting, _)| *setting == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), String> {
+        let name = name.to_lowercase();
+        let sandboxed = self.sandbox();
+        let current = match self.settings.iter_mut().find(|(setting, _)| *setting == name) {
+            Some((_, current)) => current,
+            None => return Err(format!("Unknown setting: {}", name)),
+        };
+
+        // Once a session is sandboxed, it stays sandboxed with the output target it had.
+        if sandboxed && SANDBOX_LOCKED.contains(&name.as_str()) {
+            return Err(format!("{} cannot be changed in sandbox mode", name));
+        }
+        if discriminant(current) != discriminant(&value) {
+            return Err(format!("Invalid value for {}: expected a value like {:?}", name, current));
+        }
+        match (name.as_str(), &value) {
+            ("output_format", Value::Text(format)) if !OUTPUT_FORMATS.contains(&format.as_str()) => {
+                return Err(format!("Unknown output format '{}', expected one of: {}", format, OUTPUT_FORMATS.join(", ")));
+            }
+            ("output_target", Value::Text(target)) if !OUTPUT_TARGETS.contains(&target.as_str()) => {
+                return Err(format!("Unknown output target '{}', expected one of: {}", target, OUTPUT_TARGETS.join(", ")));
+            }
+            ("math_domain_errors", Value::Text(mode)) if !MATH_DOMAIN_ERRORS.contains(&mode.as_str()) => {
+                return Err(format!("Unknown math_domain_errors mode '{}', expected one of: {}", mode, MATH_DOMAIN_ERRORS.join(", ")));
+            }
+            ("timezone", Value::Text(zone)) => {
+                TimeZone::parse(zone)?;
+            }
+            _ => {}
+        }
+
+        *current = value;
+        Ok(())
+    }
+
+    pub fn output_format(&self) -> &str {
+        match self.get("output_format") {
+            Some(Value::Text(format)) => format,
+            _ => "debug",
+        }
+    }
+
+    pub fn output_target(&self) -> &str {
+        match self.get("output_target") {
+            Some(Value::Text(target)) => target,
+            _ => "stdout",
+        }
+    }
+
+    pub fn math_domain_errors(&self) -> &str {
+        match self.get("math_domain_errors") {
+            Some(Value::Text(mode)) => mode,
+            _ => "error",
+        }
+    }
+
+    pub fn safe_updates(&self) -> bool {
+        matches!(self.get("safe_updates"), Some(Value::Boolean(true)))
+    }
+
+    pub fn sandbox(&self) -> bool {
+        matches!(self.get("sandbox"), Some(Value::Boolean(true)))
+    }
+
+    // Dot-commands that write files are refused in sandbox mode; .output is locked with the settings.
+    pub fn check_dot_command(&self, command: &str) -> Result<(), String> {
+        if self.sandbox() && command.starts_with(".capture ") && command != ".capture off" {
+            return Err(".capture cannot write files in sandbox mode".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn warn_null_comparison(&self) -> bool {
+        matches!(self.get("warn_null_comparison"), Some(Value::Boolean(true)))
+    }
+
+    pub fn print_settings(&self) {
+        if self.output_format() == "markdown" {
+            let rows: Vec<Vec<String>> = self
+                .settings
+                .iter()
+                .map(|(name, value)| vec![name.to_string(), format!("{:?}", value)])
+                .collect();
+            print!("{}", markdown_table(&["setting", "value"], &rows));
+            return;
+        }
+        for (name, value) in &self.settings {
+            println!("  {:<22} {:?}", name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandboxed() -> Session {
+        let mut session = Session::new();
+        session.set("sandbox", Value::Boolean(true)).unwrap();
+        session
+    }
+
+    #[test]
+    fn sandbox_refuses_writing_dot_commands() {
+        let session = sandboxed();
+        assert!(session.check_dot_command(".capture script.sql").is_err());
+        assert!(session.check_dot_command(".capture off").is_ok());
+        assert!(session.check_dot_command(".vars").is_ok());
+        assert!(Session::new().check_dot_command(".capture script.sql").is_ok());
+    }
+
+    #[test]
+    fn sandbox_locks_the_output_target() {
+        let mut session = sandboxed();
+        assert!(session.set("output_target", Value::Text("clipboard".to_string())).is_err());
+        assert!(session.set("sandbox", Value::Boolean(false)).is_err());
+        assert_eq!(session.output_target(), "stdout");
+        assert!(session.set("output_format", Value::Text("pretty".to_string())).is_ok());
+    }
+}
@@ -0,0 +1,337 @@
+use std::fmt;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    days: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    micros: i64,
+}
+
+// Only fixed UTC offsets are supported; there is no zone database.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TimeZone {
+    offset_seconds: i64,
+}
+
+impl Date {
+    pub fn from_days(days: i64) -> Self {
+        Date { days }
+    }
+
+    pub fn days(&self) -> i64 {
+        self.days
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (year, month, day) = parse_ymd(text.trim())?;
+        Ok(Date { days: days_from_civil(year, month, day) })
+    }
+}
+
+impl Timestamp {
+    pub fn from_micros(micros: i64) -> Self {
+        Timestamp { micros }
+    }
+
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (date_part, time_part) = match text.find([' ', 'T']) {
+            Some(index) => (&text[..index], Some(&text[index + 1..])),
+            None => (text, None),
+        };
+
+        let (year, month, day) = parse_ymd(date_part)?;
+        let seconds_of_day = match time_part {
+            Some(time) => parse_time(time)?,
+            None => 0,
+        };
+
+        let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY;
+        Ok(Timestamp { micros: seconds * MICROS_PER_SECOND + seconds_of_day })
+    }
+
+    // Parses a timestamp with an optional trailing zone ('Z', 'UTC' or
+    // '+HH:MM') and normalizes it to UTC. Without a zone, UTC is assumed.
+    pub fn parse_with_zone(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (local, zone) = split_zone(text)?;
+        let local = Timestamp::parse(local)?;
+        Ok(Timestamp { micros: local.micros - zone.offset_seconds * MICROS_PER_SECOND })
+    }
+}
+
+impl TimeZone {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let trimmed = text.trim();
+        if trimmed.eq_ignore_ascii_case("UTC") || trimmed == "Z" {
+            return Ok(TimeZone { offset_seconds: 0 });
+        }
+
+        let (sign, rest) = match trimmed.chars().next() {
+            Some('+') => (1, &trimmed[1..]),
+            Some('-') => (-1, &trimmed[1..]),
+            _ => return Err(format!("Invalid time zone '{}', expected UTC or an offset like +02:00", text)),
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None => (rest, "00"),
+        };
+        if hours.len() != 2 || minutes.len() != 2 {
+            return Err(format!("Invalid time zone '{}', expected UTC or an offset like +02:00", text));
+        }
+
+        let hours: i64 = parse_digits(hours, text)?;
+        let minutes: i64 = parse_digits(minutes, text)?;
+        if hours > 14 || minutes > 59 {
+            return Err(format!("Time zone offset '{}' is out of range", text));
+        }
+        Ok(TimeZone { offset_seconds: sign * (hours * 3600 + minutes * 60) })
+    }
+}
+
+// Calendar arithmetic for the date functions. Results stay within the years
+// 0000-9999 that literals can spell.
+impl Date {
+    // (year, month, day)
+    pub fn civil(&self) -> (i64, u32, u32) {
+        civil_from_days(self.days)
+    }
+
+    // 1 for January 1st.
+    pub fn day_of_year(&self) -> u32 {
+        let (year, _, _) = self.civil();
+        (self.days - days_from_civil(year, 1, 1) + 1) as u32
+    }
+
+    // 0 for Sunday; 1970-01-01 was a Thursday.
+    pub fn day_of_week(&self) -> u32 {
+        (self.days + 4).rem_euclid(7) as u32
+    }
+
+    // The day of month is kept, or clamped to the last day of a shorter month.
+    pub fn add_months(&self, months: i64) -> Option<Date> {
+        let (year, month, day) = self.civil();
+        let index = year.checked_mul(12)?.checked_add(month as i64 - 1)?.checked_add(months)?;
+        let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+        if !(0..=9999).contains(&year) {
+            return None;
+        }
+        Some(Date { days: days_from_civil(year, month, day.min(days_in_month(year, month))) })
+    }
+}
+
+impl Timestamp {
+    pub fn date(&self) -> Date {
+        Date { days: self.micros.div_euclid(SECONDS_PER_DAY * MICROS_PER_SECOND) }
+    }
+
+    pub fn micros_of_day(&self) -> i64 {
+        self.micros.rem_euclid(SECONDS_PER_DAY * MICROS_PER_SECOND)
+    }
+
+    // Applies a modifier of DATE and STRFTIME: '[+|-]N unit', where unit is
+    // second, minute, hour, day, month or year (or their plurals), or
+    // 'start of day', 'start of month' or 'start of year'.
+    pub fn apply_modifier(&self, modifier: &str) -> Result<Timestamp, String> {
+        let invalid = || format!("Invalid date modifier '{}'", modifier);
+        let out_of_range = || format!("Date modifier '{}' is out of range", modifier);
+        let normalized = modifier.trim().to_lowercase();
+        let date = self.date();
+
+        if let Some(unit) = normalized.strip_prefix("start of ") {
+            let (year, month, _) = date.civil();
+            let start = match unit.trim() {
+                "day" => date,
+                "month" => Date { days: days_from_civil(year, month, 1) },
+                "year" => Date { days: days_from_civil(year, 1, 1) },
+                _ => return Err(invalid()),
+            };
+            return Ok(Timestamp { micros: start.days * SECONDS_PER_DAY * MICROS_PER_SECOND });
+        }
+
+        let (amount, unit) = normalized.split_once(' ').ok_or_else(invalid)?;
+        let amount: i64 = amount.strip_prefix('+').unwrap_or(amount).parse().map_err(|_| invalid())?;
+        let unit = unit.trim();
+        let unit_micros = match unit.strip_suffix('s').unwrap_or(unit) {
+            "second" => MICROS_PER_SECOND,
+            "minute" => 60 * MICROS_PER_SECOND,
+            "hour" => 3600 * MICROS_PER_SECOND,
+            "day" => SECONDS_PER_DAY * MICROS_PER_SECOND,
+            "month" | "year" => {
+                let months = if unit.starts_with("year") { amount.checked_mul(12) } else { Some(amount) };
+                let shifted = months.and_then(|months| date.add_months(months)).ok_or_else(out_of_range)?;
+                let micros = shifted.days * SECONDS_PER_DAY * MICROS_PER_SECOND + self.micros_of_day();
+                return Ok(Timestamp { micros });
+            }
+            _ => return Err(invalid()),
+        };
+        let micros = amount
+            .checked_mul(unit_micros)
+            .and_then(|micros| self.micros.checked_add(micros))
+            .ok_or_else(out_of_range)?;
+        let shifted = Timestamp { micros };
+        if !(0..=9999).contains(&shifted.date().civil().0) {
+            return Err(out_of_range());
+        }
+        Ok(shifted)
+    }
+}
+
+impl fmt::Debug for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl fmt::Debug for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let days = self.micros.div_euclid(SECONDS_PER_DAY * MICROS_PER_SECOND);
+        let micros_of_day = self.micros.rem_euclid(SECONDS_PER_DAY * MICROS_PER_SECOND);
+        let seconds_of_day = micros_of_day / MICROS_PER_SECOND;
+
+        write!(
+            f,
+            "{:?} {:02}:{:02}:{:02}",
+            Date::from_days(days),
+            seconds_of_day / 3600,
+            seconds_of_day % 3600 / 60,
+            seconds_of_day % 60
+        )?;
+        if micros_of_day % MICROS_PER_SECOND != 0 {
+            write!(f, ".{:06}", micros_of_day % MICROS_PER_SECOND)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.offset_seconds == 0 {
+            return write!(f, "UTC");
+        }
+        let sign = if self.offset_seconds < 0 { '-' } else { '+' };
+        let offset = self.offset_seconds.abs();
+        write!(f, "{}{:02}:{:02}", sign, offset / 3600, offset % 3600 / 60)
+    }
+}
+
+fn split_zone(text: &str) -> Result<(&str, TimeZone), String> {
+    if let Some(local) = text.strip_suffix('Z') {
+        return Ok((local, TimeZone { offset_seconds: 0 }));
+    }
+    if text.len() > 3 && text[text.len() - 3..].eq_ignore_ascii_case("UTC") {
+        return Ok((text[..text.len() - 3].trim_end(), TimeZone { offset_seconds: 0 }));
+    }
+
+    // The date part itself contains '-', so only look for a sign after it.
+    let time_start = text.find([' ', 'T']).unwrap_or(text.len());
+    match text[time_start..].rfind(['+', '-']) {
+        Some(index) => {
+            let index = time_start + index;
+            Ok((text[..index].trim_end(), TimeZone::parse(&text[index..])?))
+        }
+        None => Ok((text, TimeZone { offset_seconds: 0 })),
+    }
+}
+
+fn parse_ymd(text: &str) -> Result<(i64, u32, u32), String> {
+    let parts: Vec<&str> = text.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return Err(format!("Invalid date '{}', expected YYYY-MM-DD", text));
+    }
+
+    let year: i64 = parse_digits(parts[0], text)?;
+    let month: u32 = parse_digits(parts[1], text)?;
+    let day: u32 = parse_digits(parts[2], text)?;
+
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(format!("Date '{}' is out of range", text));
+    }
+    Ok((year, month, day))
+}
+
+// Returns the time of day in microseconds.
+fn parse_time(text: &str) -> Result<i64, String> {
+    let (clock, fraction) = match text.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (text, None),
+    };
+
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() != 3 || parts.iter().any(|part| part.len() != 2) {
+        return Err(format!("Invalid time '{}', expected HH:MM:SS", text));
+    }
+
+    let hour: i64 = parse_digits(parts[0], text)?;
+    let minute: i64 = parse_digits(parts[1], text)?;
+    let second: i64 = parse_digits(parts[2], text)?;
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(format!("Time '{}' is out of range", text));
+    }
+
+    let micros = match fraction {
+        Some(fraction) if !fraction.is_empty() && fraction.len() <= 6 => {
+            let digits: i64 = parse_digits(fraction, text)?;
+            digits * 10_i64.pow(6 - fraction.len() as u32)
+        }
+        Some(_) => return Err(format!("Invalid fractional seconds in '{}'", text)),
+        None => 0,
+    };
+
+    Ok((hour * 3600 + minute * 60 + second) * MICROS_PER_SECOND + micros)
+}
+
+fn parse_digits<T: std::str::FromStr>(digits: &str, text: &str) -> Result<T, String> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid date/time '{}'", text));
+    }
+    digits.parse().map_err(|_| format!("Invalid date/time '{}'", text))
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
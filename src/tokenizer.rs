@@ -1,3 +1,17 @@
+// Reserved words; anything else made of letters, digits and '_' is an identifier.
+// Words that can follow an expression or a table name (LIMIT, USING, AT, ...)
+// must be reserved, or they would be read as an implicit alias. Any other word,
+// such as one that only introduces a statement (FETCH, RELEASE) or appears
+// inside a single clause (CURSOR, KEY), is not reserved: the parser matches it
+// with check_word, so it stays usable as a column or table name.
+pub const KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "ORDER", "GROUP", "BY", "DELETE", "UPDATE", "SET", "INSERT", "INTO",
+    "VALUES", "TO", "CREATE", "AS", "NULL", "IS", "NOT", "TABLE", "FOR", "AND", "OR", "IN", "LIKE",
+    "ESCAPE", "AT", "HAVING", "ASC", "DESC", "LIMIT", "OFFSET", "WITH", "DISTINCT", "JOIN", "INNER",
+    "ON", "LEFT", "RIGHT", "FULL", "OUTER", "CROSS", "USING", "NATURAL", "EXISTS", "UNION",
+    "INTERSECT", "EXCEPT", "ALL", "CASE", "WHEN", "THEN", "ELSE", "END",
+];
+
 #[derive(Debug, PartialEq, Clone)]
 #[allow(dead_code)]
 pub enum Token {
@@ -6,6 +20,8 @@ pub enum Token {
     Float(f64),
     Number(i64),
     StringLiteral(String),
+    Boolean(bool),
+    BlobLiteral(Vec<u8>),
     Operator(String),
     Delimiter(char),
 }
@@ -29,6 +45,10 @@ impl<'a> Tokenizer<'a> {
         self.input.get(self.current_position..)?.chars().next()
     }
 
+    fn peek_char(&self) -> Option<char> {
+        self.input.get(self.current_position + 1..)?.chars().next()
+    }
+
     fn advance(&mut self) {
         self.current_position += 1;
     }
@@ -53,20 +73,51 @@ impl<'a> Tokenizer<'a> {
         Err("Unterminated string literal".to_string())
     }
 
+    fn handle_blob_literal(&mut self) -> Result<(), String> {
+        self.advance();
+        self.advance();
+
+        let start = self.current_position;
+        while let Some(c) = self.current_char() {
+            if c == '\'' {
+                let hex = &self.input[start..self.current_position];
+                if !hex.len().is_multiple_of(2) {
+                    return Err("Blob literal must have an even number of hex digits".to_string());
+                }
+                let mut bytes = Vec::with_capacity(hex.len() / 2);
+                for i in (0..hex.len()).step_by(2) {
+                    match u8::from_str_radix(&hex[i..i + 2], 16) {
+                        Ok(byte) => bytes.push(byte),
+                        Err(_) => return Err(format!("Invalid hex digits in blob literal: {}", &hex[i..i + 2])),
+                    }
+                }
+                self.tokens.push(Token::BlobLiteral(bytes));
+                self.advance();
+                return Ok(());
+            }
+            if !c.is_ascii_hexdigit() {
+                return Err(format!("Invalid character '{}' in blob literal", c));
+            }
+            self.advance();
+        }
+        Err("Unterminated blob literal".to_string())
+    }
+
     fn handle_alphabetic(&mut self) -> Result<(), String> {
         let start = self.current_position;
         while let Some(c) = self.current_char() {
-            if c.is_alphabetic() || c == '_' {
+            if c.is_alphanumeric() || c == '_' {
                 self.advance();
             } else {
                 break;
             }
         }
 
-        let keywords: [&str; 11] = ["SELECT", "FROM", "WHERE", "ORDER", "GROUP", "DELETE", "UPDATE", "SET", "INSERT", "INTO", "VALUES"];
         let phrase = self.input[start..self.current_position].to_string();
         let upper_phrase = phrase.to_uppercase();
-        if keywords.contains(&upper_phrase.as_str()) {
+        if upper_phrase == "TRUE" || upper_phrase == "FALSE" {
+            self.tokens.push(Token::Boolean(upper_phrase == "TRUE"));
+        } else if KEYWORDS.contains(&upper_phrase.as_str()) {
             self.tokens.push(Token::Keyword(upper_phrase));
         } else {
             self.tokens.push(Token::Identifier(phrase));
@@ -78,7 +129,7 @@ impl<'a> Tokenizer<'a> {
         let start = self.current_position;
         let mut has_dot = false;
         while let Some(c) = self.current_char() {
-            if c.is_digit(10) {
+            if c.is_ascii_digit() {
                 self.advance();
             } else if c == '.' && !has_dot {
                 has_dot = true;
@@ -88,10 +139,28 @@ impl<'a> Tokenizer<'a> {
             }
         }
 
-        if has_dot {
+        // An exponent, as in 1e30 or 2.5E-3, makes the literal a float.
+        let mut has_exponent = false;
+        if matches!(self.current_char(), Some('e' | 'E')) {
+            self.advance();
+            if matches!(self.current_char(), Some('+' | '-')) {
+                self.advance();
+            }
+            let digits = self.current_position;
+            while self.current_char().is_some_and(|c| c.is_ascii_digit()) {
+                self.advance();
+            }
+            if self.current_position == digits {
+                return Err(format!("Invalid exponent in numeric literal {}", &self.input[start..self.current_position]));
+            }
+            has_exponent = true;
+        }
+
+        if has_dot || has_exponent {
             let number: Result<f64, _> = self.input[start..self.current_position].parse();
             match number {
-                Ok(value) => self.tokens.push(Token::Float(value)),
+                Ok(value) if value.is_finite() => self.tokens.push(Token::Float(value)),
+                Ok(_) => return Err(format!("Float literal {} is out of range", &self.input[start..self.current_position])),
                 Err(_) => return Err("Failed to parse float".to_string()),
             }
         } else {
@@ -109,10 +178,8 @@ impl<'a> Tokenizer<'a> {
         self.advance();
 
         if let Some(next_char) = self.current_char() {
-            if next_char == '=' {
-                operator.push(next_char);
-                self.advance();
-            } else if initial_char == '!' && next_char == '=' {
+            let is_shift = next_char == initial_char && (initial_char == '<' || initial_char == '>');
+            if next_char == '=' || is_shift {
                 operator.push(next_char);
                 self.advance();
             }
@@ -140,18 +207,19 @@ impl<'a> Tokenizer<'a> {
             match self.current_char() {
                 Some(c) if c.is_whitespace() => self.advance(),
                 Some(c) if c == '"' || c == '\'' => self.handle_literals()?,
-                Some(c) if c == ';' || c == ',' || c == '(' || c == ')' => {
+                Some(c) if c == ';' || c == ',' || c == '(' || c == ')' || c == '.' => {
                     self.tokens.push(Token::Delimiter(c));
                     self.advance();
                 }
-                Some(c) if "+-*/=".contains(c) => {
+                Some(c) if "+-*/%=".contains(c) => {
                     self.tokens.push(Token::Operator(c.to_string()));
                     self.advance();
                 }
                 Some(c) if c == '<' || c == '>' || c == '!' => self.handle_operator(c)?,
                 Some(c) if c == '&' || c == '|' => self.handle_logical_operator(c)?,
-                Some(c) if c.is_alphabetic() => self.handle_alphabetic()?,
-                Some(c) if c.is_digit(10) => self.handle_numeric()?,
+                Some(c) if (c == 'x' || c == 'X') && self.peek_char() == Some('\'') => self.handle_blob_literal()?,
+                Some(c) if c.is_alphabetic() || c == '_' => self.handle_alphabetic()?,
+                Some(c) if c.is_ascii_digit() => self.handle_numeric()?,
                 Some(_) => {
                     return Err(format!("Unrecognized token at position {}", self.current_position));
                 }
@@ -161,3 +229,28 @@ impl<'a> Tokenizer<'a> {
         Ok(&self.tokens)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+        Tokenizer::new(input).tokenize().cloned()
+    }
+
+    #[test]
+    fn exponents_are_part_of_numeric_literals() {
+        let tokens = tokenize("1e30 2.5E-3 1E+2 7").unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::Float(1e30), Token::Float(0.0025), Token::Float(100.0), Token::Number(7)]
+        );
+    }
+
+    #[test]
+    fn malformed_exponents_are_rejected() {
+        assert!(tokenize("1e").is_err());
+        assert!(tokenize("1e+x").is_err());
+        assert!(tokenize("1e400").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::evaluate::sql_literal;
+use crate::output::markdown_table;
+use crate::parser::{Parser, Query};
+use crate::session::Session;
+use crate::tokenizer::Tokenizer;
+
+// REPL variables, interpolated into statements as :name before tokenizing.
+// Values are kept as SQL text, so `.set id = 42` and `.set name = 'bob'`
+// substitute a number and a string literal respectively. `.set total = SELECT 40 + 2;`
+// stores the value the query returns, written back as a literal.
+pub struct Variables {
+    values: Vec<(String, String)>,
+}
+
+impl Variables {
+    pub fn new() -> Self {
+        Variables { values: Vec::new() }
+    }
+
+    // Parses the argument of `.set`, e.g. "limit = 10".
+    pub fn assign(&mut self, assignment: &str, session: &Session) -> Result<String, String> {
+        let (name, value) = match assignment.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => return Err("Usage: .set name = value".to_string()),
+        };
+        if !is_variable_name(name) {
+            return Err(format!("Invalid variable name '{}'", name));
+        }
+        if value.is_empty() {
+            return Err("Usage: .set name = value".to_string());
+        }
+
+        let value = value.trim_end_matches(';').trim_end().to_string();
+        let value = if value.get(..6).is_some_and(|prefix| prefix.eq_ignore_ascii_case("SELECT")) {
+            self.capture(&value, session)?
+        } else {
+            value
+        };
+        match self.values.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, current)) => *current = value,
+            None => self.values.push((name.to_string(), value)),
+        }
+        Ok(name.to_string())
+    }
+
+    // Runs the query and returns its single value as SQL text.
+    fn capture(&self, query: &str, session: &Session) -> Result<String, String> {
+        let statement = format!("{};", self.interpolate(query)?);
+        let mut tokenizer = Tokenizer::new(&statement);
+        let tokens = tokenizer.tokenize()?;
+        let query = Parser::new(tokens).parse()?;
+        if session.sandbox() {
+            query.check_sandbox()?;
+        }
+        let select = match &query {
+            Query::Select(select) if select.is_constant() => select,
+            _ => return Err("Only a SELECT without FROM can be assigned until queries read tables".to_string()),
+        };
+
+        let rows = select.evaluate_constant(session)?;
+        match rows.as_slice() {
+            [row] if row.len() == 1 => sql_literal(&row[0]),
+            _ => Err(format!("The query must return one row with one column, got {} row(s)", rows.len())),
+        }
+    }
+
+    pub fn unset(&mut self, name: &str) -> bool {
+        let before = self.values.len();
+        self.values.retain(|(existing, _)| existing != name);
+        self.values.len() != before
+    }
+
+    pub fn print(&self, markdown: bool) {
+        if markdown {
+            let rows: Vec<Vec<String>> = self.values.iter().map(|(name, value)| vec![name.clone(), value.clone()]).collect();
+            print!("{}", markdown_table(&["variable", "value"], &rows));
+            return;
+        }
+        for (name, value) in &self.values {
+            println!("  {:<22} {}", name, value);
+        }
+    }
+
+    // Replaces every :name outside quoted text, i.e. string literals and
+    // double-quoted names, with the variable's value.
+    pub fn interpolate(&self, statement: &str) -> Result<String, String> {
+        let chars: Vec<char> = statement.chars().collect();
+        let mut result = String::with_capacity(statement.len());
+        let mut quote: Option<char> = None;
+        let mut index = 0;
+
+        while index < chars.len() {
+            let c = chars[index];
+            match quote {
+                Some(open) if c == open => quote = None,
+                None if c == '\'' || c == '"' => quote = Some(c),
+                _ => {}
+            }
+            let starts_name = chars.get(index + 1).is_some_and(|next| next.is_alphabetic() || *next == '_');
+            if quote.is_some() || c != ':' || !starts_name {
+                result.push(c);
+                index += 1;
+                continue;
+            }
+
+            let start = index + 1;
+            let mut end = start;
+            while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+            let name: String = chars[start..end].iter().collect();
+            match self.values.iter().find(|(existing, _)| *existing == name) {
+                Some((_, value)) => result.push_str(value),
+                None => return Err(format!("Undefined variable :{}", name)),
+            }
+            index = end;
+        }
+
+        Ok(result)
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolation_skips_quoted_text() {
+        let mut variables = Variables::new();
+        variables.assign("id = 42", &Session::new()).unwrap();
+        assert_eq!(
+            variables.interpolate("SELECT ':id', \"a:id\" FROM t WHERE id = :id;"),
+            Ok("SELECT ':id', \"a:id\" FROM t WHERE id = 42;".to_string())
+        );
+    }
+
+    #[test]
+    fn select_results_are_stored_as_literals() {
+        let session = Session::new();
+        let mut variables = Variables::new();
+        variables.assign("base = 40", &session).unwrap();
+        variables.assign("total = SELECT :base + 2;", &session).unwrap();
+        variables.assign("name = select 'bob'", &session).unwrap();
+        assert_eq!(variables.interpolate(":total, :name"), Ok("42, 'bob'".to_string()));
+
+        assert!(variables.assign("rows = SELECT a FROM t;", &session).is_err());
+        assert!(variables.assign("pair = SELECT 1, 2;", &session).is_err());
+    }
+
+    #[test]
+    fn multibyte_values_are_not_split() {
+        let session = Session::new();
+        let mut variables = Variables::new();
+        variables.assign("accented = 'ééé'", &session).unwrap();
+        variables.assign("short = 'é'", &session).unwrap();
+        assert_eq!(variables.interpolate(":accented :short"), Ok("'ééé' 'é'".to_string()));
+    }
+}